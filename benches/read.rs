@@ -3,6 +3,7 @@ use std::{sync::Arc, time::Duration};
 use arc_swap::ArcSwap;
 use criterion::{Criterion, criterion_group, criterion_main};
 use lib::{
+    access::{hazard::HazardAccessControl, striped::StripedAccessControl},
     atomic::Atomic,
     tests::{ReadTask, WriteTask, runtime},
 };
@@ -14,6 +15,28 @@ fn cas_read(c: &mut Criterion) {
     perform(c, "Read - AtomicRef CAS", Atomic::new_cas(0, u16::MAX));
 }
 
+fn striped_read(c: &mut Criterion) {
+    perform(
+        c,
+        "Read - AtomicRef Striped",
+        Atomic::new(0, StripedAccessControl::new(u16::MAX)),
+    );
+}
+
+fn hazard_read(c: &mut Criterion) {
+    perform(
+        c,
+        "Read - AtomicRef Hazard",
+        Atomic::new(0, HazardAccessControl::new(u16::MAX)),
+    );
+}
+
+fn frozen_read(c: &mut Criterion) {
+    let target = Atomic::new_cas(0, u16::MAX);
+    target.freeze();
+    perform(c, "Read - AtomicRef Frozen", target);
+}
+
 #[cfg(feature = "benches")]
 fn arc_swap_read(c: &mut Criterion) {
     perform(c, "Read - ArcSwap", ArcSwap::from_pointee(0));
@@ -36,8 +59,15 @@ fn perform<'a, T: lib::tests::ReadWriteExt<usize> + 'static>(
 }
 
 #[cfg(feature = "benches")]
-criterion_group!(benches, cas_read, arc_swap_read);
+criterion_group!(
+    benches,
+    cas_read,
+    striped_read,
+    hazard_read,
+    frozen_read,
+    arc_swap_read
+);
 
 #[cfg(not(feature = "benches"))]
-criterion_group!(benches, cas_read);
+criterion_group!(benches, cas_read, striped_read, hazard_read, frozen_read);
 criterion_main!(benches);
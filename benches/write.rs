@@ -3,6 +3,7 @@ use std::{fmt::Debug, sync::Arc, time::Duration};
 use arc_swap::ArcSwap;
 use criterion::{Criterion, criterion_group, criterion_main};
 use lib::{
+    access::{cas::CASAccessControl, hazard::HazardAccessControl, hybrid::HybridAccessControl},
     atomic::Atomic,
     tests::{ReadTask, WriteTask, runtime},
 };
@@ -15,6 +16,30 @@ fn cas_write(c: &mut Criterion) {
     perform(c, "Write - AtomicRef CAS", Atomic::new_cas(0, u16::MAX));
 }
 
+fn cas_queued_write(c: &mut Criterion) {
+    perform(
+        c,
+        "Write - AtomicRef CAS Queued",
+        Atomic::new(0, CASAccessControl::new(u16::MAX).with_queued_writers()),
+    );
+}
+
+fn hazard_write(c: &mut Criterion) {
+    perform(
+        c,
+        "Write - AtomicRef Hazard",
+        Atomic::new(0, HazardAccessControl::new(u16::MAX)),
+    );
+}
+
+fn hybrid_write(c: &mut Criterion) {
+    perform(
+        c,
+        "Write - AtomicRef Hybrid",
+        Atomic::new(0, HybridAccessControl::new(u16::MAX)),
+    );
+}
+
 #[cfg(feature = "benches")]
 fn arc_swap_write(c: &mut Criterion) {
     perform(c, "Write - ArcSwap", ArcSwap::from_pointee(0));
@@ -53,8 +78,21 @@ fn perform<'a, T: lib::tests::ReadWriteExt<usize> + 'static>(
 }
 
 #[cfg(feature = "benches")]
-criterion_group!(benches, cas_write, arc_swap_write);
+criterion_group!(
+    benches,
+    cas_write,
+    cas_queued_write,
+    hazard_write,
+    hybrid_write,
+    arc_swap_write
+);
 
 #[cfg(not(feature = "benches"))]
-criterion_group!(benches, cas_write);
+criterion_group!(
+    benches,
+    cas_write,
+    cas_queued_write,
+    hazard_write,
+    hybrid_write
+);
 criterion_main!(benches);
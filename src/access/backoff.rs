@@ -0,0 +1,147 @@
+use crate::sync::{park_timeout, sleep, spin_loop, yield_now};
+use std::time::Duration;
+
+const SPIN_LIMIT: u32 = 6;
+
+// How a waiter in `CASAccessControl` spends the time until the state it waits for changes.
+// The access control keeps one configured instance and clones it for every wait loop, so each loop starts from the first step.
+pub trait Backoff: Clone + Send + Sync {
+    // When true, waiters whose backoff `is_completed` sleep in the access control wait queue until a reader or writer changes the state, instead of calling `wait`.
+    const PARKS: bool = false;
+
+    fn wait(&mut self);
+
+    fn is_completed(&self) -> bool {
+        false
+    }
+}
+
+// Spins with exponentially more iterations per step, never gives up the core. For dedicated cores with short critical sections.
+#[derive(Clone, Default)]
+pub struct Spin {
+    step: u32,
+}
+
+impl Backoff for Spin {
+    fn wait(&mut self) {
+        for _ in 0..1 << self.step.min(SPIN_LIMIT) {
+            spin_loop();
+        }
+        self.step = self.step.saturating_add(1);
+    }
+}
+
+// Spins exponentially and then yields the thread on every wait. Default behaviour.
+#[derive(Clone, Default)]
+pub struct SpinThenYield {
+    step: u32,
+}
+
+impl Backoff for SpinThenYield {
+    fn wait(&mut self) {
+        if self.step <= SPIN_LIMIT {
+            for _ in 0..1 << self.step {
+                spin_loop();
+            }
+            self.step += 1;
+        } else {
+            yield_now();
+        }
+    }
+}
+
+// Spins up to `spins` waits and then parks the thread up to `timeout` on every wait. For oversubscribed hosts.
+#[derive(Clone)]
+pub struct SpinThenPark {
+    spins: u32,
+    timeout: Duration,
+    step: u32,
+}
+
+impl SpinThenPark {
+    pub fn new(spins: u32, timeout: Duration) -> Self {
+        Self {
+            spins,
+            timeout,
+            step: 0,
+        }
+    }
+}
+
+impl Default for SpinThenPark {
+    fn default() -> Self {
+        Self::new(16, Duration::from_micros(50))
+    }
+}
+
+impl Backoff for SpinThenPark {
+    fn wait(&mut self) {
+        if self.step < self.spins {
+            for _ in 0..1 << self.step.min(SPIN_LIMIT) {
+                spin_loop();
+            }
+            self.step += 1;
+        } else {
+            park_timeout(self.timeout);
+        }
+    }
+}
+
+// Spins up to `spins` waits and then blocks in the access control wait queue until woken by a state change: the last reader leaving,
+// a writer handing off his turn or a phase being opened. Uses a futex on Linux. For oversubscribed hosts where timed parking still burns cores.
+#[derive(Clone)]
+pub struct Park {
+    spins: u32,
+    step: u32,
+}
+
+impl Park {
+    pub fn new(spins: u32) -> Self {
+        Self { spins, step: 0 }
+    }
+}
+
+impl Default for Park {
+    fn default() -> Self {
+        Self::new(SPIN_LIMIT)
+    }
+}
+
+impl Backoff for Park {
+    const PARKS: bool = true;
+
+    fn wait(&mut self) {
+        for _ in 0..1 << self.step.min(SPIN_LIMIT) {
+            spin_loop();
+        }
+        self.step = self.step.saturating_add(1);
+    }
+
+    fn is_completed(&self) -> bool {
+        self.step >= self.spins
+    }
+}
+
+// Sleeps the same duration on every wait.
+#[derive(Clone)]
+pub struct Sleep {
+    duration: Duration,
+}
+
+impl Sleep {
+    pub fn new(duration: Duration) -> Self {
+        Self { duration }
+    }
+}
+
+impl Default for Sleep {
+    fn default() -> Self {
+        Self::new(Duration::from_micros(100))
+    }
+}
+
+impl Backoff for Sleep {
+    fn wait(&mut self) {
+        sleep(self.duration);
+    }
+}
@@ -0,0 +1,34 @@
+use crate::sync::{Arc, AtomicBool, Ordering};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+// Shared flag to give up writes still waiting for their turn. Clones cancel together.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("write cancelled before getting the write access")
+    }
+}
+
+impl Error for Cancelled {}
@@ -1,106 +1,211 @@
+use crate::access::backoff::{Backoff, SpinThenYield};
+use crate::access::cancel::{CancelToken, Cancelled};
+use crate::access::park::WaitQueue;
+use crate::access::policy::{AdaptiveWriteLine, PhasePolicy, Priority};
+use crate::access::queue::{QueueNode, WriterQueue};
 use crate::access::{AccessGuard, AtomicAccessControl};
-use crate::sync::Contender;
-use crate::sync::{AtomicU64, Ordering};
+use crate::sync::{AtomicBool, AtomicU16, AtomicU64, Mutex, Ordering, fence, park_timeout};
 use crossbeam_utils::CachePadded;
-use std::sync::atomic::{AtomicBool, AtomicU16};
+use std::sync::PoisonError;
+use std::time::{Duration, Instant};
+
+// Longest a parked cancellable writer sleeps before checking his token again, cancelling wakes nobody.
+const CANCEL_POLL: Duration = Duration::from_millis(1);
+
+// The backoff the access control had before backoffs became pluggable, `CASReadGuard` and `CASWriteGuard` default to it as well.
+// Its `wait` now comes from the `Backoff` trait, callers need it in scope.
+#[deprecated(note = "use `lib::access::backoff::SpinThenYield`")]
+pub type BackOffStrategy = SpinThenYield;
+
+// `read_flags` packs active readers, pending readers and read slots into one word, writers are counted apart.
+// The `wide-counters` feature doubles every width for fan-outs beyond 65,535 readers or writers.
+#[cfg(not(feature = "wide-counters"))]
+mod width {
+    pub(super) type ReadFlags = u64;
+    pub(super) type AtomicReadFlags = crate::sync::AtomicU64;
+    pub(super) type Writers = u16;
+    pub(super) type AtomicWriters = crate::sync::AtomicU16;
+    pub(super) const READERS_BITS: u32 = 16;
+}
+
+#[cfg(feature = "wide-counters")]
+mod width {
+    pub(super) type ReadFlags = u128;
+    pub(super) type AtomicReadFlags = portable_atomic::AtomicU128;
+    pub(super) type Writers = u32;
+    pub(super) type AtomicWriters = crate::sync::AtomicU32;
+    pub(super) const READERS_BITS: u32 = 32;
+}
 
-const ACTIVE_READERS_MASK: u64 = 0x0000_0000_0000_FFFF;
-const READ_SLOTS_MASK: u64 = 0xFFFF_FFFF_0000_0000;
-const PENDING_READERS_MASK: u64 = 0x0000_0000_FFFF_0000;
-const NOT_ACTIVE_PENDING_READERS_MASK: u64 = !0x0000_0000_FFFF_FFFF;
+use width::{AtomicReadFlags, AtomicWriters, READERS_BITS, ReadFlags, Writers};
 
-const PENDING_READERS_SHIFT: u64 = 16;
-const READ_SLOTS_SHIFT: u64 = 32;
+// Active plus pending readers never exceed it, so neither field can overflow on its own.
+const MAX_READERS: ReadFlags = (1 << READERS_BITS) - 1;
 
-impl AccessGuard for CASReadGuard<'_> {}
-impl AccessGuard for CASWriteGuard<'_> {}
-pub struct CASReadGuard<'a> {
-    access_control_ref: &'a CASAccessControl,
+const ACTIVE_READERS_MASK: ReadFlags = MAX_READERS;
+const PENDING_READERS_MASK: ReadFlags = MAX_READERS << PENDING_READERS_SHIFT;
+const READ_SLOTS_MASK: ReadFlags = !0 << READ_SLOTS_SHIFT;
+const ACTIVE_PENDING_READERS_MASK: ReadFlags = ACTIVE_READERS_MASK | PENDING_READERS_MASK;
+const NOT_ACTIVE_PENDING_READERS_MASK: ReadFlags = !ACTIVE_PENDING_READERS_MASK;
+
+const PENDING_READERS_SHIFT: u32 = READERS_BITS;
+const READ_SLOTS_SHIFT: u32 = 2 * READERS_BITS;
+
+impl<B: Backoff> AccessGuard for CASReadGuard<'_, B> {}
+impl<B: Backoff> AccessGuard for CASWriteGuard<'_, B> {}
+pub struct CASReadGuard<'a, B: Backoff = SpinThenYield> {
+    access_control_ref: &'a CASAccessControl<B>,
 }
 
-impl<'a> CASReadGuard<'a> {
-    pub fn new(access_control_ref: &'a CASAccessControl) -> Self {
+impl<'a, B: Backoff> CASReadGuard<'a, B> {
+    pub fn new(access_control_ref: &'a CASAccessControl<B>) -> Self {
         Self { access_control_ref }
     }
 }
-impl Drop for CASReadGuard<'_> {
+impl<B: Backoff> Drop for CASReadGuard<'_, B> {
     fn drop(&mut self) {
-        self.access_control_ref
+        let old = self
+            .access_control_ref
             .read_flags
             .fetch_update(Ordering::Release, Ordering::Acquire, |old| {
                 let readers_flag = (old & ACTIVE_READERS_MASK) - 1;
                 Some((old & !ACTIVE_READERS_MASK) | readers_flag)
             })
             .expect("Always pending writers must be incremented");
+
+        // Last reader out, the initiator may be parked waiting for it.
+        if old & ACTIVE_READERS_MASK == 1 {
+            self.access_control_ref.notify_waiters();
+        }
     }
 }
 
 // Write
-pub struct CASWriteGuard<'a> {
-    access_control_ref: &'a CASAccessControl,
+pub struct CASWriteGuard<'a, B: Backoff = SpinThenYield> {
+    access_control_ref: &'a CASAccessControl<B>,
+    // Only with queued writers, the node the next writer waits to be unlocked.
+    node: Option<Box<QueueNode>>,
 }
 
-impl<'a> CASWriteGuard<'a> {
-    pub fn new(access_control_ref: &'a CASAccessControl) -> Self {
-        Self { access_control_ref }
+impl<'a, B: Backoff> CASWriteGuard<'a, B> {
+    pub fn new(access_control_ref: &'a CASAccessControl<B>) -> Self {
+        Self {
+            access_control_ref,
+            node: None,
+        }
     }
 }
-impl Drop for CASWriteGuard<'_> {
+impl<B: Backoff> Drop for CASWriteGuard<'_, B> {
     fn drop(&mut self) {
-        let old = self
-            .access_control_ref
-            .next_writer_id
-            .fetch_sub(1, Ordering::Release);
-
-        if old == 1 {
-            self.access_control_ref
-                .is_writing
-                .store(false, Ordering::Release);
+        let control = self.access_control_ref;
+        let mut node = self.node.take();
+        // Cancelled writers whose turn comes are handed off right away, in their place.
+        while let Some(abandoned) = control.hand_off(node) {
+            node = match abandoned {
+                Abandoned::Slot => None,
+                Abandoned::Node(node) => Some(node),
+            };
         }
+
+        // Either the next writer of the phase or everyone waiting for the phase to end.
+        control.notify_waiters();
     }
 }
 
-#[derive(Default)]
-pub struct BackOffStrategy {
-    raw: Option<Contender>,
+// Turn of a cancelled writer, reached while handing off.
+enum Abandoned {
+    Slot,
+    Node(Box<QueueNode>),
 }
 
-impl BackOffStrategy {
-    pub fn wait(&mut self) {
-        if self.raw.is_none() {
-            self.raw = Some(Contender::new());
-        }
-
-        unsafe { self.raw.as_mut().unwrap_unchecked() }.snooze();
-    }
+#[derive(Default)]
+struct Tickets {
+    // Handed out to writers as they arrive.
+    next: CachePadded<AtomicU64>,
+    // Ticket of the only writer allowed to reserve a write slot.
+    admitted: CachePadded<AtomicU64>,
 }
 
-pub struct CASAccessControl {
+pub struct CASAccessControl<B: Backoff = SpinThenYield> {
     // 0-16 bits hold current active readers
     // 16=32 bits hold pending registered readers
     // 32-64bits hold read slots. When writing phase starts read slots are initialized with the current pending readers to give them grace period to finish his reads.
-    read_flags: CachePadded<AtomicU64>,
+    read_flags: CachePadded<AtomicReadFlags>,
 
     // The initiator of write phase read current pending writers and assign max of sequential writes to the phase. This is to ensure convergence of reads and writes.
-    write_slots: CachePadded<AtomicU16>,
+    write_slots: CachePadded<AtomicWriters>,
     // Track the next writer to perform the action.
-    next_writer_id: CachePadded<AtomicU16>,
-    pending_writers: CachePadded<AtomicU16>,
+    next_writer_id: CachePadded<AtomicWriters>,
+    pending_writers: CachePadded<AtomicWriters>,
+    // High priority writers among the pending ones, normal writers reserve no slot while there is any.
+    pending_high: CachePadded<AtomicWriters>,
+    // High priority writers that got their slot while normal writers were pending.
+    queue_jumps: AtomicU64,
+    // Write phases opened so far.
+    write_phases: AtomicU64,
     is_writing: CachePadded<AtomicBool>,
+    // Set when a phase opens, the first writer whose turn comes waits for the readers. Usually the initiator, unless he was cancelled.
+    undrained: AtomicBool,
+    // Every waiting and later writer gives up as if cancelled.
+    closed: AtomicBool,
+    // Slots given up by cancelled writers, skipped when their turn comes. Only without queued writers.
+    cancelled_slots: Mutex<Vec<Writers>>,
+    cancelled: AtomicWriters,
 
     // Maximum number of sequential writes that can happen in each write phase. Can be in/decreased to reduce contention in read or writes.
-    max_write_line: u16,
+    // Read once by each initiator, so a change applies from the next write phase.
+    max_write_line: AtomicU16,
+    phase_policy: PhasePolicy,
+    // When set, initiators retune `max_write_line` from the readers and writers waiting for their phase.
+    adaptive: Option<AdaptiveWriteLine>,
+
+    // When set, writers of a phase wait on their own node instead of all spinning on `next_writer_id`.
+    writer_queue: Option<WriterQueue>,
+
+    // When set, writers reserve their slots, and so commit, in the order they called `write`.
+    fifo: Option<Tickets>,
+
+    // When set, a write phase running longer closes after the current writer even if write slots remain, the writers still
+    // waiting for their turn try again in a later phase. FIFO phases only stop handing out slots.
+    write_budget: Option<Duration>,
+    // Nanoseconds since `clock` at which the running write phase started.
+    phase_started: AtomicU64,
+    // Last slot of a phase closed early, the phase ends when `next_writer_id` reaches it.
+    phase_floor: AtomicWriters,
+    clock: Instant,
+
+    // Cloned by every reader and writer that has to wait for his turn.
+    backoff: B,
+    // Only used when the backoff parks.
+    waiters: WaitQueue,
 }
 
-impl Default for CASAccessControl {
+impl<B: Backoff + Default> Default for CASAccessControl<B> {
     fn default() -> Self {
         Self {
             read_flags: Default::default(),
             write_slots: Default::default(),
             next_writer_id: Default::default(),
             pending_writers: Default::default(),
+            pending_high: Default::default(),
+            queue_jumps: Default::default(),
+            write_phases: Default::default(),
             is_writing: Default::default(),
-            max_write_line: 1,
+            undrained: Default::default(),
+            closed: Default::default(),
+            cancelled_slots: Default::default(),
+            cancelled: Default::default(),
+            max_write_line: AtomicU16::new(1),
+            phase_policy: Default::default(),
+            adaptive: None,
+            writer_queue: None,
+            fifo: None,
+            write_budget: None,
+            phase_started: Default::default(),
+            phase_floor: Default::default(),
+            clock: Instant::now(),
+            backoff: Default::default(),
+            waiters: Default::default(),
         }
     }
 }
@@ -109,33 +214,218 @@ impl CASAccessControl {
     pub fn new(max_write_line: u16) -> Self {
         assert!(max_write_line > 0);
         Self {
-            max_write_line,
+            max_write_line: AtomicU16::new(max_write_line),
             ..Default::default()
         }
     }
+}
+
+impl<B: Backoff> CASAccessControl<B> {
+    // Readers and writers beyond these make `read` and `write` panic instead of silently wrapping the counters.
+    #[allow(clippy::unnecessary_cast)] // Narrowing with `wide-counters`.
+    pub const MAX_READERS: u64 = MAX_READERS as u64;
+    pub const MAX_PENDING_WRITERS: u64 = Writers::MAX as u64;
+
+    pub fn with_backoff<N: Backoff>(self, backoff: N) -> CASAccessControl<N> {
+        CASAccessControl {
+            read_flags: self.read_flags,
+            write_slots: self.write_slots,
+            next_writer_id: self.next_writer_id,
+            pending_writers: self.pending_writers,
+            pending_high: self.pending_high,
+            queue_jumps: self.queue_jumps,
+            write_phases: self.write_phases,
+            is_writing: self.is_writing,
+            undrained: self.undrained,
+            closed: self.closed,
+            cancelled_slots: self.cancelled_slots,
+            cancelled: self.cancelled,
+            max_write_line: self.max_write_line,
+            phase_policy: self.phase_policy,
+            adaptive: self.adaptive,
+            writer_queue: self.writer_queue,
+            fifo: self.fifo,
+            write_budget: self.write_budget,
+            phase_started: self.phase_started,
+            phase_floor: self.phase_floor,
+            clock: self.clock,
+            backoff,
+            waiters: self.waiters,
+        }
+    }
+
+    pub(crate) fn backoff(&self) -> &B {
+        &self.backoff
+    }
+
+    pub fn with_phase_policy(mut self, phase_policy: PhasePolicy) -> Self {
+        self.phase_policy = phase_policy;
+        self
+    }
+
+    pub fn with_adaptive_write_line(mut self, adaptive: AdaptiveWriteLine) -> Self {
+        self.set_max_write_line(adaptive.clamp(self.max_write_line()));
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    // Hands the write access over through a queue of writers, each spinning on his own cache line.
+    pub fn with_queued_writers(mut self) -> Self {
+        self.writer_queue = Some(WriterQueue::default());
+        self
+    }
+
+    // Writes commit strictly in arrival order, across write phases too.
+    pub fn with_fifo_writers(mut self) -> Self {
+        self.fifo = Some(Tickets::default());
+        self
+    }
+
+    pub fn with_write_budget(mut self, write_budget: Duration) -> Self {
+        self.write_budget = Some(write_budget);
+        self
+    }
+
+    fn elapsed_nanos(&self) -> u64 {
+        self.clock.elapsed().as_nanos() as u64
+    }
+
+    fn write_budget_exceeded(&self) -> bool {
+        self.write_budget.is_some_and(|budget| {
+            let started = self.phase_started.load(Ordering::Relaxed);
+            self.elapsed_nanos().saturating_sub(started) >= budget.as_nanos() as u64
+        })
+    }
+
+    // Writers waiting for a write slot.
+    pub fn pending_writers(&self) -> u64 {
+        self.pending_writers.load(Ordering::Acquire).into()
+    }
+
+    // Readers waiting for a write phase to end.
+    #[allow(clippy::unnecessary_cast)] // Narrowing with `wide-counters`.
+    pub fn pending_readers(&self) -> u64 {
+        ((self.read_flags.load(Ordering::Acquire) & PENDING_READERS_MASK) >> PENDING_READERS_SHIFT)
+            as u64
+    }
+
+    // Tickets handed out to FIFO writers so far, always none without them.
+    pub fn tickets_issued(&self) -> u64 {
+        self.fifo
+            .as_ref()
+            .map_or(0, |fifo| fifo.next.load(Ordering::Relaxed))
+    }
+
+    // High priority writers among the pending ones.
+    pub fn pending_high(&self) -> u64 {
+        self.pending_high.load(Ordering::Acquire).into()
+    }
+
+    // Times a high priority writer got his slot ahead of pending normal writers.
+    pub fn queue_jumps(&self) -> u64 {
+        self.queue_jumps.load(Ordering::Relaxed)
+    }
+
+    pub fn write_phases(&self) -> u64 {
+        self.write_phases.load(Ordering::Relaxed)
+    }
+
+    pub fn max_write_line(&self) -> u16 {
+        self.max_write_line.load(Ordering::Relaxed)
+    }
+
+    // Takes effect from the next write phase, the running one keeps the slots it was given.
+    pub fn set_max_write_line(&self, max_write_line: u16) {
+        assert!(max_write_line > 0);
+        self.max_write_line.store(max_write_line, Ordering::Relaxed);
+    }
+
+    // Whether a writer waiting since `waiting_since` may try to open a new write phase.
+    fn may_open_phase(&self, waiting_since: Option<Instant>) -> bool {
+        let no_readers =
+            || self.read_flags.load(Ordering::Acquire) & ACTIVE_PENDING_READERS_MASK == 0;
+        match self.phase_policy {
+            PhasePolicy::Alternating | PhasePolicy::WriterPreferring => true,
+            PhasePolicy::ReaderPreferring => no_readers(),
+            PhasePolicy::Bounded(max_wait) => {
+                no_readers() || waiting_since.is_some_and(|since| since.elapsed() >= max_wait)
+            }
+        }
+    }
+
+    // Whether a reader must let pending writers open their phase before getting in.
+    fn defers_to_writers(&self) -> bool {
+        self.phase_policy == PhasePolicy::WriterPreferring
+            && self.pending_writers.load(Ordering::Acquire) > 0
+    }
+
+    // Whether a normal writer must let the pending high priority ones reserve their slots first.
+    fn yields_to_priority(&self, high: bool) -> bool {
+        !high && self.pending_high.load(Ordering::Acquire) > 0
+    }
+
+    // Taken before checking the state waited for, so a change after the check is never slept through.
+    fn wait_epoch(&self) -> u32 {
+        if B::PARKS { self.waiters.epoch() } else { 0 }
+    }
+
+    fn snooze(&self, backoff: &mut B, epoch: u32) {
+        if B::PARKS && backoff.is_completed() {
+            self.waiters.wait(epoch);
+        } else {
+            backoff.wait();
+        }
+    }
+
+    fn snooze_cancellable(&self, backoff: &mut B, epoch: u32, token: Option<&CancelToken>) {
+        if token.is_some() && B::PARKS && backoff.is_completed() {
+            park_timeout(CANCEL_POLL);
+        } else {
+            self.snooze(backoff, epoch);
+        }
+    }
+
+    // The writer that closed the control isn't turned away by it.
+    fn gives_up(&self, token: Option<&CancelToken>, closing: bool) -> bool {
+        token.is_some_and(CancelToken::is_cancelled)
+            || !closing && self.closed.load(Ordering::Acquire)
+    }
+
+    fn notify_waiters(&self) {
+        if B::PARKS {
+            self.waiters.notify_all();
+        }
+    }
 
     fn inc_pending_writers(&self) {
-        self.pending_writers.fetch_add(1, Ordering::Release);
+        self.pending_writers
+            .fetch_update(Ordering::Release, Ordering::Acquire, |pending| {
+                pending.checked_add(1)
+            })
+            .expect("Too many pending writers, the `wide-counters` feature widens the counter");
     }
 
     fn inc_pending_readers(&self) {
         self.read_flags
             .fetch_update(Ordering::Release, Ordering::Acquire, |old| {
-                let pending_readers_flag =
-                    (((old & PENDING_READERS_MASK) >> PENDING_READERS_SHIFT) + 1)
-                        << PENDING_READERS_SHIFT;
+                let pending_readers = (old & PENDING_READERS_MASK) >> PENDING_READERS_SHIFT;
+                if (old & ACTIVE_READERS_MASK) + pending_readers >= MAX_READERS {
+                    return None;
+                }
+                let pending_readers_flag = (pending_readers + 1) << PENDING_READERS_SHIFT;
                 Some((old & !PENDING_READERS_MASK) | pending_readers_flag)
             })
-            .expect("Always pending writers must be incremented");
+            .expect("Too many readers, the `wide-counters` feature widens the counters");
     }
 
     fn dec_pending_writers(&self) {
         self.pending_writers.fetch_sub(1, Ordering::Release);
     }
 
-    fn try_reserve_write_slot(&self) -> Option<u16> {
+    fn try_reserve_write_slot(&self) -> Option<Writers> {
+        // Acquires on success too: the count repeats across phases, so a stale first load can still succeed against the new phase.
         self.write_slots
-            .fetch_update(Ordering::Release, Ordering::Acquire, |slots| {
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |slots| {
                 if slots == 0 { None } else { Some(slots - 1) }
             })
             .ok()
@@ -163,12 +453,14 @@ impl CASAccessControl {
         result.is_ok()
     }
 
-    fn initialize_read_slots(&self, slots_size: u64) {
+    fn initialize_read_slots(&self, slots_size: ReadFlags) {
         self.read_flags
             .fetch_update(Ordering::Release, Ordering::Acquire, |old| {
                 Some(old | (slots_size << READ_SLOTS_SHIFT))
             })
             .expect("Always read slots must be initialized");
+
+        self.notify_waiters();
     }
 
     fn initialize_read(&self) {
@@ -204,61 +496,231 @@ impl CASAccessControl {
             })
             .expect("Always pending writers must be incremented");
 
-        (old_read_flags & READ_SLOTS_MASK) >> READ_SLOTS_SHIFT != 0
+        let reserved = (old_read_flags & READ_SLOTS_MASK) >> READ_SLOTS_SHIFT != 0;
+        if !reserved && old_read_flags & ACTIVE_READERS_MASK == 1 {
+            self.notify_waiters();
+        }
+        reserved
     }
 
-    fn init_write_slot(&self, slots_size: u16) {
-        self.write_slots.store(slots_size, Ordering::Release);
+    fn acquire_write(
+        &self,
+        priority: Priority,
+        token: Option<&CancelToken>,
+        closing: bool,
+    ) -> Result<CASWriteGuard<'_, B>, Cancelled> {
+        loop {
+            if let Some(guard) = self.acquire_turn(priority, token, closing)? {
+                return Ok(guard);
+            }
+        }
     }
-}
 
-impl AtomicAccessControl for CASAccessControl {
-    fn write(&self) -> impl AccessGuard {
+    // `None` once the turn was given back to come again in a later phase, the phase ran out of write budget before it came.
+    fn acquire_turn(
+        &self,
+        priority: Priority,
+        token: Option<&CancelToken>,
+        closing: bool,
+    ) -> Result<Option<CASWriteGuard<'_, B>>, Cancelled> {
+        // FIFO writers are only let in by ticket, none can go ahead of the others.
+        let high = priority == Priority::High && self.fifo.is_none();
         self.inc_pending_writers();
+        // Only once the increment can't panic anymore, a skipped ticket would hold the later ones back for good and a
+        // leftover high priority writer the normal ones.
+        let ticket = self
+            .fifo
+            .as_ref()
+            .map(|fifo| fifo.next.fetch_add(1, Ordering::Relaxed));
+        if high {
+            self.pending_high.fetch_add(1, Ordering::Release);
+        }
 
         let slot_idx;
         let initiator;
-        let mut backoff = BackOffStrategy::default();
+        let mut cancelled = false;
+        let mut node = None;
+        let mut queued = false;
+        let mut phase_writers = 0;
+        let mut backoff = self.backoff.clone();
+        let waiting_since = matches!(self.phase_policy, PhasePolicy::Bounded(_)).then(Instant::now);
+
+        // Only the writer holding the oldest ticket may reserve a slot, so slots go out in arrival order.
+        // Cancelled writers wait as well to pass their turn on, the tickets ahead are all making progress.
+        if let (Some(fifo), Some(ticket)) = (&self.fifo, ticket) {
+            loop {
+                let epoch = self.wait_epoch();
+                if fifo.admitted.load(Ordering::Acquire) == ticket {
+                    break;
+                }
+                self.snooze(&mut backoff, epoch);
+            }
+            backoff = self.backoff.clone();
+        }
 
         // Initialize Write Phase.
         loop {
-            if self.is_writing.load(Ordering::Acquire) {
+            let epoch = self.wait_epoch();
+            if self.gives_up(token, closing) {
+                cancelled = true;
+                slot_idx = None;
+                initiator = false;
+                break;
+            } else if self.yields_to_priority(high) {
+                // Neither opens a phase nor reserves a slot until the high priority writers have theirs.
+            } else if self.is_writing.load(Ordering::Acquire) {
                 // [1..=SLOTS_SIZE]
                 if let Some(val) = self.try_reserve_write_slot() {
-                    slot_idx = val;
+                    slot_idx = Some(val);
                     initiator = false;
                     break;
                 }
-            } else if !self.is_writing.swap(true, Ordering::Acquire) {
+            } else if self.may_open_phase(waiting_since)
+                && self
+                    .is_writing
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
                 initiator = true;
-                let pending_writers = self.pending_writers.load(Ordering::Acquire);
-                let slots_size = pending_writers.min(self.max_write_line);
+                self.write_phases.fetch_add(1, Ordering::Relaxed);
+                // Queued before any slot is handed out, so every writer of the phase lines up behind him.
+                if let Some(queue) = &self.writer_queue {
+                    node = Some(QueueNode::new());
+                    queued = queue.enqueue(node.as_deref().expect("Always node is just set"));
+                }
+                // Modified, not just loaded, for `reserve_counted_slot` to find the phase opened if he wasn't seen gone.
+                let pending_writers = self.pending_writers.fetch_add(0, Ordering::AcqRel);
+                let slots_size = pending_writers.min(Writers::from(self.max_write_line()));
+                phase_writers = pending_writers;
+                if self.write_budget.is_some() {
+                    self.phase_started
+                        .store(self.elapsed_nanos(), Ordering::Relaxed);
+                }
 
+                self.undrained.store(true, Ordering::Relaxed);
                 self.init_write_slot(slots_size - 1);
-                slot_idx = slots_size;
-                self.next_writer_id.store(slot_idx, Ordering::Release);
+                slot_idx = Some(slots_size);
+                // Swapped, not stored, to take over whatever a writer cancelled meanwhile wrote, see `abandon_turn`.
+                self.next_writer_id.swap(slots_size, Ordering::AcqRel);
+                self.notify_waiters();
                 break;
             } else if let Some(val) = self.try_reserve_write_slot() {
-                slot_idx = val;
+                slot_idx = Some(val);
                 initiator = false;
                 break;
             }
 
-            backoff.wait();
+            self.snooze_cancellable(&mut backoff, epoch, token);
         }
 
+        if high {
+            if !cancelled
+                && self.pending_writers.load(Ordering::Acquire)
+                    > self.pending_high.load(Ordering::Acquire)
+            {
+                self.queue_jumps.fetch_add(1, Ordering::Relaxed);
+            }
+            self.pending_high.fetch_sub(1, Ordering::Release);
+            self.notify_waiters();
+        }
         self.dec_pending_writers();
+        // Still takes a slot the phase may have counted him for, to give it up as a reserved one.
+        let slot_idx = if cancelled {
+            self.reserve_counted_slot(&mut backoff)
+        } else {
+            slot_idx
+        };
+
+        if let Some(queue) = self
+            .writer_queue
+            .as_ref()
+            .filter(|_| !initiator && slot_idx.is_some())
+        {
+            node = Some(QueueNode::new());
+            queued = queue.enqueue(node.as_deref().expect("Always node is just set"));
+        }
+        // Admitted once queued, the queue must follow the ticket order as well.
+        if let (Some(fifo), Some(ticket)) = (&self.fifo, ticket) {
+            fifo.admitted.store(ticket + 1, Ordering::Release);
+            self.notify_waiters();
+            backoff = self.backoff.clone();
+        }
+
+        let Some(slot_idx) = slot_idx else {
+            self.notify_waiters();
+            return Err(Cancelled);
+        };
+        if cancelled {
+            self.abandon_turn(slot_idx, node, queued);
+            self.notify_waiters();
+            return Err(Cancelled);
+        }
+
+        // Waits for the writer ahead, of this phase or the last of the previous one still leaving.
+        if queued {
+            let queue_node = node.as_deref().expect("Always queued writers hold a node");
+            loop {
+                let epoch = self.wait_epoch();
+                if !queue_node.is_locked() {
+                    break;
+                }
+                if self.gives_up(token, closing) {
+                    self.abandon_turn(slot_idx, node, queued);
+                    return Err(Cancelled);
+                }
+                if self.yields_turn() {
+                    self.abandon_turn(slot_idx, node, queued);
+                    return Ok(None);
+                }
+                self.snooze_cancellable(&mut backoff, epoch, token);
+            }
+        }
 
         // Instead to initialize guaranteed read slots at the last write, initialize after writing flag to true to know how much time await to start writing. (Initiator).
         // Only initiator wait to read full finished, the others will wait until his turn.
         if initiator {
+            // Pairs with the fence in `read`: either the reader sees the phase started or the initiator sees the reader already active.
+            fence(Ordering::SeqCst);
             let pending_readers = (self.read_flags.load(Ordering::Acquire) & PENDING_READERS_MASK)
                 >> PENDING_READERS_SHIFT;
-            if pending_readers > 0 {
+            if pending_readers > 0 && self.phase_policy != PhasePolicy::WriterPreferring {
                 self.initialize_read_slots(pending_readers);
             }
+            if let Some(adaptive) = &self.adaptive {
+                let line = adaptive.tune(
+                    self.max_write_line(),
+                    pending_readers > 0,
+                    phase_writers.into(),
+                );
+                self.set_max_write_line(line);
+            }
+        } else if self.writer_queue.is_none() {
+            loop {
+                let epoch = self.wait_epoch();
+                // Checked before the turn, the writer handing it off may have used the budget up.
+                if self.yields_turn() {
+                    self.abandon_turn(slot_idx, node, queued);
+                    return Ok(None);
+                }
+                if self.next_writer_id.load(Ordering::Acquire) == slot_idx {
+                    break;
+                }
+                if self.gives_up(token, closing) {
+                    self.abandon_turn(slot_idx, node, queued);
+                    return Err(Cancelled);
+                }
+
+                self.snooze_cancellable(&mut backoff, epoch, token);
+            }
+        }
 
+        let guard = CASWriteGuard {
+            access_control_ref: self,
+            node,
+        };
+        if self.undrained.load(Ordering::Relaxed) && self.undrained.swap(false, Ordering::Acquire) {
             loop {
+                let epoch = self.wait_epoch();
                 let readers_flag = self.read_flags.load(Ordering::Acquire);
 
                 let readers = readers_flag & ACTIVE_READERS_MASK;
@@ -267,28 +729,178 @@ impl AtomicAccessControl for CASAccessControl {
                 if readers == 0 && read_slots == 0 {
                     break;
                 }
+                if self.gives_up(token, closing) {
+                    // The next writer of the phase waits for the readers instead.
+                    self.undrained.store(true, Ordering::Relaxed);
+                    drop(guard);
+                    return Err(Cancelled);
+                }
 
-                backoff.wait();
+                self.snooze_cancellable(&mut backoff, epoch, token);
             }
-        } else {
-            while self.next_writer_id.load(Ordering::Acquire) != slot_idx {
-                backoff.wait();
+        }
+
+        Ok(Some(guard))
+    }
+
+    // FIFO writers keep their turn, going back would let the later tickets ahead.
+    fn yields_turn(&self) -> bool {
+        self.fifo.is_none() && self.write_budget_exceeded()
+    }
+
+    fn init_write_slot(&self, slots_size: Writers) {
+        self.write_slots.store(slots_size, Ordering::Release);
+    }
+
+    // Read slots of a phase whose readers nobody waited for, the pending readers get in once it ends instead.
+    fn clear_read_slots(&self) {
+        self.read_flags
+            .fetch_update(Ordering::Release, Ordering::Acquire, |old| {
+                Some(old & !READ_SLOTS_MASK)
+            })
+            .expect("Always read slots must be cleared");
+    }
+
+    // Ends the turn of the writer holding it. Returns the next turn if it belongs to a cancelled writer.
+    fn hand_off(&self, node: Option<Box<QueueNode>>) -> Option<Abandoned> {
+        // The writer handing off is the last to run, the slots nobody reserved yet go back to the next phases and the reserved
+        // ones are given back by their writers, see `yields_turn`.
+        if self.write_budget_exceeded() {
+            let withdrawn = self.write_slots.swap(0, Ordering::AcqRel);
+            if withdrawn > 0 {
+                self.phase_floor.store(withdrawn, Ordering::Relaxed);
             }
         }
 
-        CASWriteGuard::new(self)
+        // Read before handing off, the next writer may already close the phase.
+        let floor = self.phase_floor.load(Ordering::Relaxed);
+        // Pairs with `abandon_turn`: either this writer sees the slot cancelled or its writer sees his turn came.
+        let old = self.next_writer_id.fetch_sub(1, Ordering::AcqRel);
+        let closed = old - 1 == floor;
+        if closed {
+            if floor > 0 {
+                self.next_writer_id.store(0, Ordering::Release);
+                self.phase_floor.store(0, Ordering::Relaxed);
+            }
+            if self.undrained.load(Ordering::Relaxed)
+                && self.undrained.swap(false, Ordering::Relaxed)
+            {
+                self.clear_read_slots();
+            }
+            self.is_writing.store(false, Ordering::Release);
+        }
+
+        match (&self.writer_queue, node) {
+            (Some(queue), Some(node)) => queue.release(&node).map(Abandoned::Node),
+            (None, _) if !closed && self.take_cancelled(old - 1) => Some(Abandoned::Slot),
+            _ => None,
+        }
+    }
+
+    // Called by a cancelled writer once no longer pending. A phase opened before may have counted him, its slots are only
+    // known once it set up the turns.
+    fn reserve_counted_slot(&self, backoff: &mut B) -> Option<Writers> {
+        // Either this comes first and the initiator sees the writer gone, or it comes after his and sees the phase opened.
+        self.pending_writers.fetch_add(0, Ordering::Acquire);
+        // Parks like the other waits, a cancelled writer spinning could starve the initiator. Woken once the turns are set up
+        // or the phase ended.
+        loop {
+            let epoch = self.wait_epoch();
+            if !self.is_writing.load(Ordering::Acquire) {
+                return None;
+            }
+            if self.next_writer_id.load(Ordering::Acquire) > 0 {
+                return self.try_reserve_write_slot();
+            }
+            self.snooze(backoff, epoch);
+        }
+    }
+
+    fn take_cancelled(&self, slot_idx: Writers) -> bool {
+        if self.cancelled.load(Ordering::Acquire) == 0 {
+            return false;
+        }
+        let mut slots = self
+            .cancelled_slots
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match slots.iter().position(|slot| *slot == slot_idx) {
+            Some(pos) => {
+                slots.swap_remove(pos);
+                self.cancelled.fetch_sub(1, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Gives up a reserved slot. Whoever reaches the turn first, the writer ahead handing off or this one, hands it on.
+    fn abandon_turn(&self, slot_idx: Writers, node: Option<Box<QueueNode>>, queued: bool) {
+        let turn = match node {
+            Some(node) if queued => node.abandon().map(Some),
+            Some(node) => Some(Some(node)),
+            None => {
+                self.cancelled_slots
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(slot_idx);
+                self.cancelled.fetch_add(1, Ordering::Release);
+                // Modified, not just loaded, so a hand-off coming after it finds the slot cancelled.
+                (self.next_writer_id.fetch_add(0, Ordering::AcqRel) == slot_idx
+                    && self.take_cancelled(slot_idx))
+                .then_some(None)
+            }
+        };
+        if let Some(node) = turn {
+            drop(CASWriteGuard {
+                access_control_ref: self,
+                node,
+            });
+        }
+    }
+}
+
+impl<B: Backoff> AtomicAccessControl for CASAccessControl<B> {
+    fn write(&self) -> impl AccessGuard {
+        self.write_priority(Priority::Normal)
+    }
+
+    fn write_priority(&self, priority: Priority) -> impl AccessGuard {
+        self.acquire_write(priority, None, false)
+            .expect("Always writes without token get the access until closed")
+    }
+
+    fn write_cancellable(&self, token: &CancelToken) -> Result<impl AccessGuard, Cancelled> {
+        self.acquire_write(Priority::Normal, Some(token), false)
+    }
+
+    fn try_write_priority(&self, priority: Priority) -> Result<impl AccessGuard, Cancelled> {
+        self.acquire_write(priority, None, false)
+    }
+
+    fn write_closed(&self) -> impl AccessGuard {
+        self.acquire_write(Priority::Normal, None, true)
+            .expect("Always the closing writer gets the access")
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        // Parked waiters only look at the state again once woken.
+        self.notify_waiters();
     }
 
     fn read(&self) -> impl AccessGuard {
         self.inc_pending_readers();
-        let mut backoff = BackOffStrategy::default();
+        let mut backoff = self.backoff.clone();
         loop {
+            let epoch = self.wait_epoch();
             if self.is_writing.load(Ordering::Acquire) {
                 if self.try_reserve_read_slot() {
                     break;
                 }
-            } else {
+            } else if !self.defers_to_writers() {
                 self.initialize_read();
+                fence(Ordering::SeqCst);
 
                 // Stale Read due to change. Must try get slot.
                 if !self.is_writing.load(Ordering::Acquire) || self.try_reserve_read_slot_or_reset()
@@ -297,7 +909,7 @@ impl AtomicAccessControl for CASAccessControl {
                 }
             }
 
-            backoff.wait();
+            self.snooze(&mut backoff, epoch);
         }
 
         CASReadGuard::new(self)
@@ -0,0 +1,141 @@
+use crate::access::backoff::{Backoff, SpinThenYield};
+use crate::access::cancel::{CancelToken, Cancelled};
+use crate::access::cas::CASAccessControl;
+use crate::access::garbage::Garbage;
+use crate::access::policy::Priority;
+use crate::access::{AccessGuard, AtomicAccessControl};
+use crate::sync::{Arc, AtomicU64, Mutex, Ordering, fence};
+use crossbeam_utils::CachePadded;
+use std::collections::VecDeque;
+use std::sync::PoisonError;
+
+// Epoch based reclamation. Readers pin the current epoch by counting themselves in its slot and load the pointer, with
+// no wait. Writers serialize among themselves in a `CASAccessControl` no reader ever enters, swap, and instead of
+// waiting for readers keep the replaced version as garbage tagged with the epoch seen after the swap.
+// The epoch only advances once nobody is pinned in the previous one, so while a reader stays pinned at `e` it can't go
+// past `e + 1`, and garbage tagged `r` is released once the epoch reaches `r + 2`. A stalled reader holds back every
+// release meanwhile, writes never wait for it.
+pub struct EpochAccessControl<B: Backoff = SpinThenYield> {
+    writers: CASAccessControl<B>,
+    epoch: CachePadded<AtomicU64>,
+    pinned: [CachePadded<AtomicU64>; 3],
+    garbage: Mutex<VecDeque<(u64, Garbage)>>,
+}
+
+impl Default for EpochAccessControl {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl EpochAccessControl {
+    pub fn new(max_write_line: u16) -> Self {
+        Self::with_control(CASAccessControl::new(max_write_line))
+    }
+}
+
+impl<B: Backoff> EpochAccessControl<B> {
+    pub fn with_control(writers: CASAccessControl<B>) -> Self {
+        Self {
+            writers,
+            epoch: Default::default(),
+            pinned: Default::default(),
+            garbage: Default::default(),
+        }
+    }
+
+    // Replaced versions still waiting for the readers that may hold them.
+    pub fn pending_garbage(&self) -> usize {
+        self.garbage
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    fn slot(&self, epoch: u64) -> &AtomicU64 {
+        &self.pinned[(epoch % 3) as usize]
+    }
+
+    // Only called by writers, so a single thread advances at a time.
+    fn try_advance(&self) -> u64 {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        if self.slot(epoch + 2).load(Ordering::SeqCst) == 0 {
+            self.epoch.store(epoch + 1, Ordering::SeqCst);
+            epoch + 1
+        } else {
+            epoch
+        }
+    }
+}
+
+impl<B: Backoff> AtomicAccessControl for EpochAccessControl<B> {
+    fn write(&self) -> impl AccessGuard {
+        self.writers.write()
+    }
+
+    fn write_priority(&self, priority: Priority) -> impl AccessGuard {
+        self.writers.write_priority(priority)
+    }
+
+    fn write_cancellable(&self, token: &CancelToken) -> Result<impl AccessGuard, Cancelled> {
+        self.writers.write_cancellable(token)
+    }
+
+    fn try_write_priority(&self, priority: Priority) -> Result<impl AccessGuard, Cancelled> {
+        self.writers.try_write_priority(priority)
+    }
+
+    fn close(&self) {
+        self.writers.close();
+    }
+
+    fn write_closed(&self) -> impl AccessGuard {
+        self.writers.write_closed()
+    }
+
+    fn read(&self) -> impl AccessGuard {
+        loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let pinned = self.slot(epoch);
+            pinned.fetch_add(1, Ordering::SeqCst);
+            // Pairs with the fence in `retire`: either the writer tags with this epoch or later, or the reader sees the new version.
+            fence(Ordering::SeqCst);
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                return EpochReadGuard { pinned };
+            }
+            // Advanced meanwhile, the slot may already be checked as drained.
+            pinned.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    fn retire<T>(&self, old: &Arc<T>) {
+        fence(Ordering::SeqCst);
+        let tag = self.epoch.load(Ordering::SeqCst);
+        let epoch = self.try_advance();
+
+        let released: Vec<_> = {
+            let mut garbage = self.garbage.lock().unwrap_or_else(PoisonError::into_inner);
+            garbage.push_back((tag, Garbage::new(old.clone())));
+
+            let releasable = garbage
+                .iter()
+                .take_while(|(tag, _)| tag + 2 <= epoch)
+                .count();
+            garbage.drain(..releasable).collect()
+        };
+        // Released out of the lock, dropping values may take a while.
+        drop(released);
+    }
+}
+
+pub struct EpochReadGuard<'a> {
+    pinned: &'a AtomicU64,
+}
+
+impl AccessGuard for EpochReadGuard<'_> {}
+
+impl Drop for EpochReadGuard<'_> {
+    fn drop(&mut self) {
+        self.pinned.fetch_sub(1, Ordering::Release);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::sync::Arc;
+
+// Reference to a replaced version of any type, released on drop.
+pub(crate) struct Garbage {
+    raw: *const (),
+    release: unsafe fn(*const ()),
+}
+
+// Only moved between the writers of the atomic the value lived in, as `Atomic` itself does.
+unsafe impl Send for Garbage {}
+
+impl Garbage {
+    pub(crate) fn new<T>(value: Arc<T>) -> Self {
+        unsafe fn release<T>(raw: *const ()) {
+            drop(unsafe { Arc::from_raw(raw as *const T) });
+        }
+
+        Self {
+            raw: Arc::into_raw(value) as *const (),
+            release: release::<T>,
+        }
+    }
+
+    pub(crate) fn raw(&self) -> *const () {
+        self.raw
+    }
+}
+
+impl Drop for Garbage {
+    fn drop(&mut self) {
+        unsafe { (self.release)(self.raw) }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::access::backoff::{Backoff, SpinThenYield};
+use crate::access::cancel::{CancelToken, Cancelled};
+use crate::access::cas::CASAccessControl;
+use crate::access::garbage::Garbage;
+use crate::access::policy::Priority;
+use crate::access::{AccessGuard, AtomicAccessControl};
+use crate::sync::{Arc, AtomicPtr, Mutex, Ordering, fence};
+use crossbeam_utils::CachePadded;
+use std::ptr;
+use std::sync::PoisonError;
+
+static SLOT_GEN: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+thread_local! {
+    static SLOT_HINT: usize = SLOT_GEN.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+}
+
+// Hazard pointer reclamation. Readers claim a slot, publish the pointer they are about to upgrade in it and check it
+// is still current, with no wait on writers. Writers serialize among themselves in a `CASAccessControl`, keep the
+// replaced versions as garbage and, once there are more than slots, release every one no slot publishes. A stalled
+// reader holds back at most the single version it published, so unreleased versions never exceed the slots plus one.
+// Plain `read` access, which can't say what it will load, falls back to the read phase of the inner control.
+pub struct HazardAccessControl<B: Backoff = SpinThenYield> {
+    writers: CASAccessControl<B>,
+    hazards: Box<[CachePadded<AtomicPtr<()>>]>,
+    garbage: Mutex<Vec<Garbage>>,
+}
+
+impl Default for HazardAccessControl {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl HazardAccessControl {
+    pub fn new(max_write_line: u16) -> Self {
+        Self::with_control(CASAccessControl::new(max_write_line))
+    }
+}
+
+impl<B: Backoff> HazardAccessControl<B> {
+    // Two slots per available core, readers past that wait for a free one.
+    pub fn with_control(writers: CASAccessControl<B>) -> Self {
+        let slots = std::thread::available_parallelism().map_or(1, |cores| cores.get()) * 2;
+        Self::with_slots(writers, slots)
+    }
+
+    pub fn with_slots(writers: CASAccessControl<B>, slots: usize) -> Self {
+        assert!(slots > 0);
+        Self {
+            writers,
+            hazards: (0..slots)
+                .map(|_| CachePadded::new(AtomicPtr::new(ptr::null_mut())))
+                .collect(),
+            garbage: Default::default(),
+        }
+    }
+
+    pub fn slots(&self) -> usize {
+        self.hazards.len()
+    }
+
+    // Replaced versions still waiting for the readers that may hold them.
+    pub fn pending_garbage(&self) -> usize {
+        self.garbage
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    // Takes a free slot publishing `raw`, starting from the one of this thread.
+    fn claim(&self, raw: *mut ()) -> &AtomicPtr<()> {
+        let start = SLOT_HINT.with(|hint| *hint);
+        let mut backoff = SpinThenYield::default();
+        loop {
+            for idx in 0..self.hazards.len() {
+                let hazard = &self.hazards[(start + idx) % self.hazards.len()];
+                if hazard
+                    .compare_exchange(ptr::null_mut(), raw, Ordering::SeqCst, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    return hazard;
+                }
+            }
+            backoff.wait();
+        }
+    }
+}
+
+impl<B: Backoff> AtomicAccessControl for HazardAccessControl<B> {
+    fn write(&self) -> impl AccessGuard {
+        self.writers.write()
+    }
+
+    fn write_priority(&self, priority: Priority) -> impl AccessGuard {
+        self.writers.write_priority(priority)
+    }
+
+    fn write_cancellable(&self, token: &CancelToken) -> Result<impl AccessGuard, Cancelled> {
+        self.writers.write_cancellable(token)
+    }
+
+    fn try_write_priority(&self, priority: Priority) -> Result<impl AccessGuard, Cancelled> {
+        self.writers.try_write_priority(priority)
+    }
+
+    fn close(&self) {
+        self.writers.close();
+    }
+
+    fn write_closed(&self) -> impl AccessGuard {
+        self.writers.write_closed()
+    }
+
+    fn read(&self) -> impl AccessGuard {
+        self.writers.read()
+    }
+
+    fn protect<T>(&self, current: &AtomicPtr<T>) -> (impl AccessGuard, *mut T) {
+        let mut raw = current.load(Ordering::Acquire);
+        let hazard = self.claim(raw as *mut ());
+        loop {
+            // Pairs with the fence in `retire`: either the writer sees the published pointer or the reader sees it replaced.
+            fence(Ordering::SeqCst);
+            let now = current.load(Ordering::Acquire);
+            if now == raw {
+                return (HazardReadGuard { hazard }, raw);
+            }
+            raw = now;
+            hazard.store(raw as *mut (), Ordering::SeqCst);
+        }
+    }
+
+    fn retire<T>(&self, old: &Arc<T>) {
+        let released: Vec<_> = {
+            let mut garbage = self.garbage.lock().unwrap_or_else(PoisonError::into_inner);
+            garbage.push(Garbage::new(old.clone()));
+            if garbage.len() <= self.hazards.len() {
+                return;
+            }
+
+            fence(Ordering::SeqCst);
+            let published: Vec<_> = self
+                .hazards
+                .iter()
+                .map(|hazard| hazard.load(Ordering::SeqCst) as *const ())
+                .filter(|raw| !raw.is_null())
+                .collect();
+            let (kept, released) = garbage
+                .drain(..)
+                .partition(|garbage| published.contains(&garbage.raw()));
+            *garbage = kept;
+            released
+        };
+        // Released out of the lock, dropping values may take a while.
+        drop(released);
+    }
+}
+
+pub struct HazardReadGuard<'a> {
+    hazard: &'a AtomicPtr<()>,
+}
+
+impl AccessGuard for HazardReadGuard<'_> {}
+
+impl Drop for HazardReadGuard<'_> {
+    fn drop(&mut self) {
+        self.hazard.store(ptr::null_mut(), Ordering::Release);
+    }
+}
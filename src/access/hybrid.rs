@@ -0,0 +1,267 @@
+use crate::access::backoff::{Backoff, SpinThenYield};
+use crate::access::cancel::{CancelToken, Cancelled};
+use crate::access::cas::CASAccessControl;
+use crate::access::policy::{HybridSwitch, Priority};
+use crate::access::{AccessGuard, AtomicAccessControl};
+use crate::sync::{
+    Arc, AtomicBool, AtomicU32, AtomicU64, Ordering, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+use crossbeam_utils::CachePadded;
+use std::sync::{PoisonError, TryLockError};
+
+// Runs on `CASAccessControl` while contention is light and falls back to an OS lock, whose waiters sleep instead of
+// spinning, once the waits grow too long. Every operation takes the guard of the current path and checks the path didn't
+// change meanwhile. A writer switches paths holding the write access of both, so no guard of the path left is outstanding.
+pub struct HybridAccessControl<B: Backoff = SpinThenYield> {
+    fast: CASAccessControl<Watched<B>>,
+    // Recovered when poisoned, like the one of `LockAccessControl`.
+    lock: RwLock<()>,
+    slow: CachePadded<AtomicBool>,
+    switch: HybridSwitch,
+    // Only touched by the writer holding the access.
+    window_writes: AtomicU32,
+    switches: AtomicU64,
+    // Shared with the backoffs of `fast`, which count the long waits of the CAS path into it.
+    contended: Arc<CachePadded<AtomicU64>>,
+}
+
+impl HybridAccessControl {
+    pub fn new(max_write_line: u16) -> Self {
+        Self::with_control(CASAccessControl::new(max_write_line))
+    }
+}
+
+impl<B: Backoff> HybridAccessControl<B> {
+    pub fn with_control(inner: CASAccessControl<B>) -> Self {
+        Self::with_switch(inner, HybridSwitch::default())
+    }
+
+    pub fn with_switch(inner: CASAccessControl<B>, switch: HybridSwitch) -> Self {
+        let contended = Arc::new(CachePadded::new(AtomicU64::new(0)));
+        let backoff = Watched {
+            inner: inner.backoff().clone(),
+            steps: 0,
+            spins: switch.spins(),
+            reported: false,
+            contended: contended.clone(),
+        };
+        Self {
+            fast: inner.with_backoff(backoff),
+            lock: RwLock::new(()),
+            slow: CachePadded::new(AtomicBool::new(false)),
+            switch,
+            window_writes: Default::default(),
+            switches: Default::default(),
+            contended,
+        }
+    }
+
+    pub fn is_locking(&self) -> bool {
+        self.slow.load(Ordering::Relaxed)
+    }
+
+    pub fn switches(&self) -> u64 {
+        self.switches.load(Ordering::Relaxed)
+    }
+
+    fn write_by<'a, F: AccessGuard>(
+        &'a self,
+        fast_write: impl Fn() -> Result<F, Cancelled>,
+        lock_write: impl Fn() -> Result<RwLockWriteGuard<'a, ()>, Cancelled>,
+    ) -> Result<HybridGuard<F, RwLockWriteGuard<'a, ()>>, Cancelled> {
+        loop {
+            if self.slow.load(Ordering::Acquire) {
+                let guard = lock_write()?;
+                if !self.slow.load(Ordering::Relaxed) {
+                    continue;
+                }
+                if !self.tally_write(true) {
+                    return Ok(HybridGuard::Slow(guard));
+                }
+                // Only operations that saw the CAS path before the last switch are in there, they leave once they look again.
+                return Ok(match fast_write() {
+                    Ok(fast) => {
+                        self.switch_path(false);
+                        HybridGuard::Both(fast, guard)
+                    }
+                    Err(Cancelled) => HybridGuard::Slow(guard),
+                });
+            }
+
+            let guard = fast_write()?;
+            if self.slow.load(Ordering::Relaxed) {
+                continue;
+            }
+            if !self.tally_write(false) {
+                return Ok(HybridGuard::Fast(guard));
+            }
+            return Ok(match lock_write() {
+                Ok(lock) => {
+                    self.switch_path(true);
+                    HybridGuard::Both(guard, lock)
+                }
+                Err(Cancelled) => HybridGuard::Fast(guard),
+            });
+        }
+    }
+
+    // Called by the writer holding the access. Whether the window he ends calls for the other path.
+    fn tally_write(&self, slow: bool) -> bool {
+        let writes = self.window_writes.load(Ordering::Relaxed) + 1;
+        if writes < self.switch.window() {
+            self.window_writes.store(writes, Ordering::Relaxed);
+            return false;
+        }
+        self.window_writes.store(0, Ordering::Relaxed);
+        let contended = self.contended.swap(0, Ordering::Relaxed);
+        if slow {
+            contended <= u64::from(self.switch.to_cas())
+        } else {
+            contended >= u64::from(self.switch.to_lock())
+        }
+    }
+
+    fn switch_path(&self, slow: bool) {
+        self.slow.store(slow, Ordering::Release);
+        self.switches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // A taken lock counts as contention, its waiter sleeps where he would have spun on the CAS path.
+    fn write_lock(&self) -> RwLockWriteGuard<'_, ()> {
+        match self.lock.try_write() {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => {
+                self.contended.fetch_add(1, Ordering::Relaxed);
+                self.lock.write().unwrap_or_else(PoisonError::into_inner)
+            }
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        }
+    }
+
+    fn write_lock_cancellable(
+        &self,
+        token: &CancelToken,
+    ) -> Result<RwLockWriteGuard<'_, ()>, Cancelled> {
+        let mut backoff = SpinThenYield::default();
+        let mut contended = false;
+        loop {
+            if token.is_cancelled() {
+                return Err(Cancelled);
+            }
+            match self.lock.try_write() {
+                Ok(guard) => return Ok(guard),
+                Err(TryLockError::WouldBlock) => {
+                    if !contended {
+                        contended = true;
+                        self.contended.fetch_add(1, Ordering::Relaxed);
+                    }
+                    backoff.wait();
+                }
+                Err(TryLockError::Poisoned(poisoned)) => return Ok(poisoned.into_inner()),
+            }
+        }
+    }
+
+    fn read_lock(&self) -> RwLockReadGuard<'_, ()> {
+        match self.lock.try_read() {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => {
+                self.contended.fetch_add(1, Ordering::Relaxed);
+                self.lock.read().unwrap_or_else(PoisonError::into_inner)
+            }
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl<B: Backoff> AtomicAccessControl for HybridAccessControl<B> {
+    fn write(&self) -> impl AccessGuard {
+        self.write_priority(Priority::Normal)
+    }
+
+    // Priorities only apply on the CAS path, the lock ignores them.
+    fn write_priority(&self, priority: Priority) -> impl AccessGuard {
+        self.write_by(
+            || Ok(self.fast.write_priority(priority)),
+            || Ok(self.write_lock()),
+        )
+        .expect("Always writes without token get the access")
+    }
+
+    fn write_cancellable(&self, token: &CancelToken) -> Result<impl AccessGuard, Cancelled> {
+        self.write_by(
+            || self.fast.write_cancellable(token),
+            || self.write_lock_cancellable(token),
+        )
+    }
+
+    // Writers already waiting for the lock get it, to fail once they hold it.
+    fn try_write_priority(&self, priority: Priority) -> Result<impl AccessGuard, Cancelled> {
+        self.write_by(
+            || self.fast.try_write_priority(priority),
+            || Ok(self.write_lock()),
+        )
+    }
+
+    fn close(&self) {
+        self.fast.close();
+    }
+
+    fn write_closed(&self) -> impl AccessGuard {
+        self.write_by(|| Ok(self.fast.write_closed()), || Ok(self.write_lock()))
+            .expect("Always the closing writer gets the access")
+    }
+
+    fn read(&self) -> impl AccessGuard {
+        loop {
+            if self.slow.load(Ordering::Acquire) {
+                let guard = self.read_lock();
+                if self.slow.load(Ordering::Relaxed) {
+                    return HybridGuard::Slow(guard);
+                }
+            } else {
+                let guard = self.fast.read();
+                if !self.slow.load(Ordering::Relaxed) {
+                    return HybridGuard::Fast(guard);
+                }
+            }
+        }
+    }
+}
+
+pub enum HybridGuard<F: AccessGuard, S: AccessGuard> {
+    Fast(F),
+    Slow(S),
+    // Held by the writer switching paths.
+    Both(F, S),
+}
+
+impl<F: AccessGuard, S: AccessGuard> AccessGuard for HybridGuard<F, S> {}
+
+// Backoff of the CAS path, counts each wait loop running past `spins` steps into the counter of its control.
+#[derive(Clone)]
+struct Watched<B> {
+    inner: B,
+    steps: u32,
+    spins: u32,
+    reported: bool,
+    contended: Arc<CachePadded<AtomicU64>>,
+}
+
+impl<B: Backoff> Backoff for Watched<B> {
+    const PARKS: bool = B::PARKS;
+
+    fn wait(&mut self) {
+        self.inner.wait();
+        self.steps = self.steps.saturating_add(1);
+        // A completed parking backoff isn't called again, the control parks the waiter instead.
+        if !self.reported && (self.steps >= self.spins || self.inner.is_completed()) {
+            self.reported = true;
+            self.contended.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn is_completed(&self) -> bool {
+        self.inner.is_completed()
+    }
+}
@@ -1,18 +1,57 @@
+use crate::access::backoff::{Backoff, SpinThenYield};
+use crate::access::cancel::{CancelToken, Cancelled};
 use crate::access::{AccessGuard, AtomicAccessControl};
-use crate::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::sync::{AtomicU64, Ordering, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{PoisonError, TryLockError};
 
+// The lock guards no data, a write closure panicking under it leaves nothing to poison. The `PanicPolicy` of the atomic
+// decides what follows.
 #[derive(Default)]
 pub struct LockAccessControl {
     lock: RwLock<()>,
+    polling: AtomicU64,
+}
+
+impl LockAccessControl {
+    // Cancellable writers that found the lock taken and poll it.
+    pub fn polling_writers(&self) -> u64 {
+        self.polling.load(Ordering::Relaxed)
+    }
 }
 
 impl AtomicAccessControl for LockAccessControl {
     fn write(&self) -> impl AccessGuard {
-        self.lock.write().expect("Always lock is locked")
+        self.lock.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // A blocked `write` can't be given up, cancellable writers poll the lock instead.
+    fn write_cancellable(&self, token: &CancelToken) -> Result<impl AccessGuard, Cancelled> {
+        let mut backoff = SpinThenYield::default();
+        let mut polling = false;
+        let acquired = loop {
+            if token.is_cancelled() {
+                break Err(Cancelled);
+            }
+            match self.lock.try_write() {
+                Ok(guard) => break Ok(guard),
+                Err(TryLockError::WouldBlock) => {
+                    if !polling {
+                        polling = true;
+                        self.polling.fetch_add(1, Ordering::Relaxed);
+                    }
+                    backoff.wait();
+                }
+                Err(TryLockError::Poisoned(poisoned)) => break Ok(poisoned.into_inner()),
+            }
+        };
+        if polling {
+            self.polling.fetch_sub(1, Ordering::Relaxed);
+        }
+        acquired
     }
 
     fn read(&self) -> impl AccessGuard {
-        self.lock.read().expect("Always lock is locked")
+        self.lock.read().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
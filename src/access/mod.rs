@@ -1,9 +1,56 @@
+pub mod backoff;
+pub mod cancel;
 pub mod cas;
+pub mod epoch;
+mod garbage;
+pub mod hazard;
+pub mod hybrid;
 pub mod lock;
+mod park;
+pub mod policy;
+mod queue;
+pub mod striped;
+pub mod wait_free;
+
+use crate::access::cancel::{CancelToken, Cancelled};
+use crate::access::policy::Priority;
+use crate::sync::{Arc, AtomicPtr, Ordering};
 
 pub trait AccessGuard {}
 
 pub trait AtomicAccessControl: Send + Sync {
     fn write(&self) -> impl AccessGuard;
+
+    // Controls without a notion of priority let every writer in as `write` does.
+    fn write_priority(&self, _priority: Priority) -> impl AccessGuard {
+        self.write()
+    }
+    // Gives up with `Cancelled` once the token is cancelled while waiting. Controls that can't give up only check it before.
+    fn write_cancellable(&self, token: &CancelToken) -> Result<impl AccessGuard, Cancelled> {
+        if token.is_cancelled() {
+            return Err(Cancelled);
+        }
+        Ok(self.write())
+    }
+    // Same as `write_priority`, but fails with `Cancelled` once the control is closed, writers already waiting included.
+    fn try_write_priority(&self, priority: Priority) -> Result<impl AccessGuard, Cancelled> {
+        Ok(self.write_priority(priority))
+    }
+    // Releases the waiting writers and fails the later ones. Controls whose waiters can't give up let them through.
+    fn close(&self) {}
+    // Write access for whoever closed the control, the close doesn't turn it away. Writers already holding it finish first.
+    fn write_closed(&self) -> impl AccessGuard {
+        self.write()
+    }
     fn read(&self) -> impl AccessGuard;
+
+    // Read access together with the pointer loaded under it, for controls that must know what the reader is about to upgrade.
+    fn protect<T>(&self, current: &AtomicPtr<T>) -> (impl AccessGuard, *mut T) {
+        let guard = self.read();
+        (guard, current.load(Ordering::Acquire))
+    }
+
+    // Called by the writer, still holding the write access, with the version it just swapped out. Controls whose readers
+    // aren't held back by writes must not let it be released before every reader that may have loaded it holds his own reference.
+    fn retire<T>(&self, _old: &Arc<T>) {}
 }
@@ -0,0 +1,83 @@
+use crate::sync::{AtomicU32, Ordering, fence};
+#[cfg(not(all(target_os = "linux", not(loom))))]
+use crate::sync::{Condvar, Mutex};
+#[cfg(not(all(target_os = "linux", not(loom))))]
+use std::sync::PoisonError;
+
+// Waiters read the epoch before checking the state they wait for, and only sleep while it still holds that value.
+// Notifiers change the state first and bump the epoch after, so a change the waiter missed always makes his sleep return at once.
+#[derive(Default)]
+pub(crate) struct WaitQueue {
+    epoch: AtomicU32,
+    sleepers: AtomicU32,
+    #[cfg(not(all(target_os = "linux", not(loom))))]
+    lock: Mutex<()>,
+    #[cfg(not(all(target_os = "linux", not(loom))))]
+    cond: Condvar,
+}
+
+impl WaitQueue {
+    pub(crate) fn epoch(&self) -> u32 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn wait(&self, epoch: u32) {
+        self.sleepers.fetch_add(1, Ordering::SeqCst);
+        // Pairs with the fence in `notify_all`: either the notifier sees this sleeper or the sleep sees the new epoch.
+        fence(Ordering::SeqCst);
+        self.sleep(epoch);
+        self.sleepers.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    pub(crate) fn notify_all(&self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+        if self.sleepers.load(Ordering::SeqCst) > 0 {
+            self.wake_all();
+        }
+    }
+
+    #[cfg(all(target_os = "linux", not(loom)))]
+    fn sleep(&self, epoch: u32) {
+        // Returns at once if the epoch moved, EINTR and spurious wake ups just send the waiter back to check his state.
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                self.epoch.as_ptr(),
+                libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+                epoch,
+                std::ptr::null::<libc::timespec>(),
+            );
+        }
+    }
+
+    #[cfg(all(target_os = "linux", not(loom)))]
+    fn wake_all(&self) {
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                self.epoch.as_ptr(),
+                libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+                i32::MAX,
+            );
+        }
+    }
+
+    #[cfg(not(all(target_os = "linux", not(loom))))]
+    fn sleep(&self, epoch: u32) {
+        let guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.epoch.load(Ordering::SeqCst) == epoch {
+            drop(
+                self.cond
+                    .wait(guard)
+                    .unwrap_or_else(PoisonError::into_inner),
+            );
+        }
+    }
+
+    #[cfg(not(all(target_os = "linux", not(loom))))]
+    fn wake_all(&self) {
+        drop(self.lock.lock().unwrap_or_else(PoisonError::into_inner));
+        self.cond.notify_all();
+    }
+}
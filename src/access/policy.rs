@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+// Decides who goes first when readers and writers of a `CASAccessControl` contend. Writes of a phase are always capped by `max_write_line`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PhasePolicy {
+    // Each write phase first lets in the readers pending when it started and then runs his writes, so readers and writers alternate in batches.
+    // Nobody starves: a reader waits at most for the running phase, a writer for the readers pending at phase start plus the phases ahead of him.
+    #[default]
+    Alternating,
+    // Readers hold back while any writer is pending and write phases grant no read slots.
+    // Writers only wait for the readers already active; readers starve under a continuous stream of writes.
+    WriterPreferring,
+    // A write phase is only opened when no reader is active or pending.
+    // Readers only wait for an already running phase; writers starve under a continuous stream of reads.
+    ReaderPreferring,
+    // Like `ReaderPreferring` until the writer has waited the given time, then he opens the phase as `Alternating` does.
+    // Writers wait at most about the bound plus the readers pending at that moment, checked each time the writer wakes up.
+    Bounded(Duration),
+}
+
+// Order in which pending writers of a `CASAccessControl` reserve their write slots.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Priority {
+    #[default]
+    Normal,
+    // Reserves a slot of the running or next write phase ahead of every pending `Normal` writer. Phases still grant their read
+    // slots, so readers don't starve, but a continuous stream of high priority writes starves normal ones.
+    High,
+}
+
+// Bounds for the adaptive `max_write_line` of a `CASAccessControl`. Each write phase initiator halves the line when readers were left
+// waiting for the phase, favoring read latency, and doubles it when more writers pend than the line admits, favoring write throughput.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptiveWriteLine {
+    min: u16,
+    max: u16,
+}
+
+impl AdaptiveWriteLine {
+    pub fn new(min: u16, max: u16) -> Self {
+        assert!(min > 0 && min <= max);
+        Self { min, max }
+    }
+
+    pub fn min(&self) -> u16 {
+        self.min
+    }
+
+    pub fn max(&self) -> u16 {
+        self.max
+    }
+
+    pub(crate) fn clamp(&self, line: u16) -> u16 {
+        line.clamp(self.min, self.max)
+    }
+
+    pub(crate) fn tune(&self, line: u16, readers_waited: bool, pending_writers: u64) -> u16 {
+        if readers_waited {
+            self.clamp(line / 2)
+        } else if pending_writers > u64::from(line) {
+            self.clamp(line.saturating_mul(2))
+        } else {
+            line
+        }
+    }
+}
+
+// When a `HybridAccessControl` moves between its CAS and lock paths. Every `window` writes it counts the waits of the window that
+// ran past `spins` backoff steps on the CAS path, or found the lock taken on the lock path. At least `to_lock` of them move it to
+// the lock, at most `to_cas` back to CAS; the gap between both keeps it from flapping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HybridSwitch {
+    spins: u32,
+    window: u32,
+    to_lock: u32,
+    to_cas: u32,
+}
+
+impl HybridSwitch {
+    pub fn new(spins: u32, window: u32, to_lock: u32, to_cas: u32) -> Self {
+        assert!(spins > 0 && window > 0 && to_cas < to_lock);
+        Self {
+            spins,
+            window,
+            to_lock,
+            to_cas,
+        }
+    }
+
+    pub fn spins(&self) -> u32 {
+        self.spins
+    }
+
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    pub fn to_lock(&self) -> u32 {
+        self.to_lock
+    }
+
+    pub fn to_cas(&self) -> u32 {
+        self.to_cas
+    }
+}
+
+impl Default for HybridSwitch {
+    fn default() -> Self {
+        Self::new(16, 64, 16, 2)
+    }
+}
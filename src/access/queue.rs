@@ -0,0 +1,102 @@
+use crate::sync::{AtomicPtr, AtomicU8, Ordering, spin_loop};
+use crossbeam_utils::CachePadded;
+use std::ptr;
+
+const WAITING: u8 = 0;
+const GRANTED: u8 = 1;
+const ABANDONED: u8 = 2;
+
+// Node of a waiting writer, boxed so it stays put while the guard owning it moves around.
+pub(crate) struct QueueNode {
+    state: CachePadded<AtomicU8>,
+    next: AtomicPtr<QueueNode>,
+}
+
+impl QueueNode {
+    pub(crate) fn new() -> Box<Self> {
+        Box::new(Self {
+            state: CachePadded::new(AtomicU8::new(WAITING)),
+            next: AtomicPtr::new(ptr::null_mut()),
+        })
+    }
+
+    pub(crate) fn is_locked(&self) -> bool {
+        self.state.load(Ordering::Acquire) == WAITING
+    }
+
+    // Leaves the node to the predecessor, who hands the turn on when it comes. Gives it back if the turn already came.
+    pub(crate) fn abandon(self: Box<Self>) -> Option<Box<Self>> {
+        let raw = Box::into_raw(self);
+        match unsafe { &*raw }.state.compare_exchange(
+            WAITING,
+            ABANDONED,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => None,
+            Err(_) => Some(unsafe { Box::from_raw(raw) }),
+        }
+    }
+
+    fn as_ptr(&self) -> *mut QueueNode {
+        self as *const _ as *mut _
+    }
+}
+
+// MCS queue of the writers holding write slots. Each one spins on his own node until his predecessor unlocks it, so a
+// hand-off only touches the cache line of the next writer.
+#[derive(Default)]
+pub(crate) struct WriterQueue {
+    tail: CachePadded<AtomicPtr<QueueNode>>,
+}
+
+impl WriterQueue {
+    // Appends the node, returns whether it has to wait for a predecessor to unlock it.
+    pub(crate) fn enqueue(&self, node: &QueueNode) -> bool {
+        let prev = self.tail.swap(node.as_ptr(), Ordering::AcqRel);
+        if prev.is_null() {
+            return false;
+        }
+        // The predecessor can't leave before linking to us, he waits for it in `release`.
+        unsafe { &*prev }
+            .next
+            .store(node.as_ptr(), Ordering::Release);
+        true
+    }
+
+    // Unlocks the successor, if any. Returns his node when he abandoned it, his turn must be handed on in his place.
+    pub(crate) fn release(&self, node: &QueueNode) -> Option<Box<QueueNode>> {
+        let mut next = node.next.load(Ordering::Acquire);
+        if next.is_null() {
+            if self
+                .tail
+                .compare_exchange(
+                    node.as_ptr(),
+                    ptr::null_mut(),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                return None;
+            }
+            // A successor already swapped the tail but didn't link yet.
+            loop {
+                next = node.next.load(Ordering::Acquire);
+                if !next.is_null() {
+                    break;
+                }
+                spin_loop();
+            }
+        }
+        match unsafe { &*next }.state.compare_exchange(
+            WAITING,
+            GRANTED,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => None,
+            Err(_) => Some(unsafe { Box::from_raw(next) }),
+        }
+    }
+}
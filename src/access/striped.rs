@@ -0,0 +1,167 @@
+use crate::access::backoff::{Backoff, SpinThenYield};
+use crate::access::cancel::{CancelToken, Cancelled};
+use crate::access::cas::CASAccessControl;
+use crate::access::policy::Priority;
+use crate::access::{AccessGuard, AtomicAccessControl};
+use crate::sync::{AtomicBool, AtomicU32, AtomicU64, Ordering, fence};
+use crossbeam_utils::CachePadded;
+use std::time::Instant;
+
+// Writers keep reads on the slow path for this many times the cost of their last revocation.
+const INHIBIT_FACTOR: u32 = 9;
+
+static STRIPE_GEN: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+thread_local! {
+    static STRIPE: usize = STRIPE_GEN.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+}
+
+// Read-biased access control in the style of BRAVO. While `read_bias` holds, readers only count themselves in the stripe
+// of their thread and never touch a shared line. The first writer revokes the bias, waits for the stripes to drain and
+// from then on readers go through the inner `CASAccessControl` until a slow reader finds the inhibition elapsed.
+pub struct StripedAccessControl<B: Backoff = SpinThenYield> {
+    inner: CASAccessControl<B>,
+    read_bias: CachePadded<AtomicBool>,
+    stripes: Box<[CachePadded<AtomicU32>]>,
+    // Nanoseconds since `clock` before which readers must not restore the bias.
+    inhibit_until: AtomicU64,
+    clock: Instant,
+}
+
+impl StripedAccessControl {
+    pub fn new(max_write_line: u16) -> Self {
+        Self::with_control(CASAccessControl::new(max_write_line))
+    }
+}
+
+impl<B: Backoff> StripedAccessControl<B> {
+    // One stripe per available core, threads are spread over them round robin.
+    pub fn with_control(inner: CASAccessControl<B>) -> Self {
+        let stripes = std::thread::available_parallelism()
+            .map_or(1, |cores| cores.get())
+            .next_power_of_two();
+        Self::with_stripes(inner, stripes)
+    }
+
+    pub fn with_stripes(inner: CASAccessControl<B>, stripes: usize) -> Self {
+        assert!(stripes > 0);
+        Self {
+            inner,
+            read_bias: CachePadded::new(AtomicBool::new(true)),
+            stripes: (0..stripes).map(|_| Default::default()).collect(),
+            inhibit_until: Default::default(),
+            clock: Instant::now(),
+        }
+    }
+
+    pub fn is_read_biased(&self) -> bool {
+        self.read_bias.load(Ordering::Relaxed)
+    }
+
+    fn stripe(&self) -> &AtomicU32 {
+        let idx = STRIPE.with(|stripe| *stripe) % self.stripes.len();
+        &self.stripes[idx]
+    }
+
+    fn elapsed_nanos(&self) -> u64 {
+        self.clock.elapsed().as_nanos() as u64
+    }
+
+    // Only called while holding the inner write access, so no reader can restore the bias meanwhile.
+    fn revoke_read_bias(&self) {
+        self.read_bias.store(false, Ordering::Relaxed);
+        // Pairs with the fence in `read`: either the reader sees the bias revoked or the writer sees his stripe.
+        fence(Ordering::SeqCst);
+
+        let started = self.elapsed_nanos();
+        let mut backoff = SpinThenYield::default();
+        for stripe in self.stripes.iter() {
+            while stripe.load(Ordering::Acquire) != 0 {
+                backoff.wait();
+            }
+        }
+        let now = self.elapsed_nanos();
+        self.inhibit_until.store(
+            now + (now - started) * u64::from(INHIBIT_FACTOR),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+impl<B: Backoff> AtomicAccessControl for StripedAccessControl<B> {
+    fn write(&self) -> impl AccessGuard {
+        self.write_priority(Priority::Normal)
+    }
+
+    fn write_priority(&self, priority: Priority) -> impl AccessGuard {
+        let guard = self.inner.write_priority(priority);
+        if self.read_bias.load(Ordering::Relaxed) {
+            self.revoke_read_bias();
+        }
+        guard
+    }
+
+    fn write_cancellable(&self, token: &CancelToken) -> Result<impl AccessGuard, Cancelled> {
+        let guard = self.inner.write_cancellable(token)?;
+        if self.read_bias.load(Ordering::Relaxed) {
+            self.revoke_read_bias();
+        }
+        Ok(guard)
+    }
+
+    fn try_write_priority(&self, priority: Priority) -> Result<impl AccessGuard, Cancelled> {
+        let guard = self.inner.try_write_priority(priority)?;
+        if self.read_bias.load(Ordering::Relaxed) {
+            self.revoke_read_bias();
+        }
+        Ok(guard)
+    }
+
+    fn close(&self) {
+        self.inner.close();
+    }
+
+    fn write_closed(&self) -> impl AccessGuard {
+        let guard = self.inner.write_closed();
+        if self.read_bias.load(Ordering::Relaxed) {
+            self.revoke_read_bias();
+        }
+        guard
+    }
+
+    fn read(&self) -> impl AccessGuard {
+        if self.read_bias.load(Ordering::Acquire) {
+            let stripe = self.stripe();
+            stripe.fetch_add(1, Ordering::Relaxed);
+            fence(Ordering::SeqCst);
+            if self.read_bias.load(Ordering::Relaxed) {
+                return StripedReadGuard::Fast(stripe);
+            }
+            stripe.fetch_sub(1, Ordering::Release);
+        }
+
+        let guard = self.inner.read();
+        // No writer holds the inner access while we read, so restoring the bias can't race a revocation.
+        if !self.read_bias.load(Ordering::Relaxed)
+            && self.elapsed_nanos() >= self.inhibit_until.load(Ordering::Relaxed)
+        {
+            self.read_bias.store(true, Ordering::Relaxed);
+        }
+        StripedReadGuard::Slow(guard)
+    }
+}
+
+pub enum StripedReadGuard<'a, G: AccessGuard> {
+    Fast(&'a AtomicU32),
+    Slow(G),
+}
+
+impl<G: AccessGuard> AccessGuard for StripedReadGuard<'_, G> {}
+
+impl<G: AccessGuard> Drop for StripedReadGuard<'_, G> {
+    fn drop(&mut self) {
+        if let StripedReadGuard::Fast(stripe) = self {
+            stripe.fetch_sub(1, Ordering::Release);
+        }
+    }
+}
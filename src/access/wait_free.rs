@@ -0,0 +1,98 @@
+use crate::access::backoff::{Backoff, SpinThenYield};
+use crate::access::cancel::{CancelToken, Cancelled};
+use crate::access::cas::CASAccessControl;
+use crate::access::policy::Priority;
+use crate::access::{AccessGuard, AtomicAccessControl};
+use crate::sync::{Arc, AtomicU64, Ordering, fence};
+use crossbeam_utils::CachePadded;
+
+// Readers never wait: they count themselves in the reader counter of the current epoch parity and load the pointer.
+// Writers serialize among themselves in a `CASAccessControl` no reader ever enters. After swapping, the writer flips the
+// epoch and drains the parity readers just left, then flips again and drains the other one, as readers that loaded the
+// epoch late may sit in either. New readers always go to the parity not being drained, so each wait is bounded by the
+// readers already in flight.
+pub struct WaitFreeAccessControl<B: Backoff = SpinThenYield> {
+    writers: CASAccessControl<B>,
+    epoch: CachePadded<AtomicU64>,
+    readers: [CachePadded<AtomicU64>; 2],
+}
+
+impl Default for WaitFreeAccessControl {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl WaitFreeAccessControl {
+    pub fn new(max_write_line: u16) -> Self {
+        Self::with_control(CASAccessControl::new(max_write_line))
+    }
+}
+
+impl<B: Backoff> WaitFreeAccessControl<B> {
+    pub fn with_control(writers: CASAccessControl<B>) -> Self {
+        Self {
+            writers,
+            epoch: Default::default(),
+            readers: Default::default(),
+        }
+    }
+}
+
+impl<B: Backoff> AtomicAccessControl for WaitFreeAccessControl<B> {
+    fn write(&self) -> impl AccessGuard {
+        self.writers.write()
+    }
+
+    fn write_priority(&self, priority: Priority) -> impl AccessGuard {
+        self.writers.write_priority(priority)
+    }
+
+    fn write_cancellable(&self, token: &CancelToken) -> Result<impl AccessGuard, Cancelled> {
+        self.writers.write_cancellable(token)
+    }
+
+    fn try_write_priority(&self, priority: Priority) -> Result<impl AccessGuard, Cancelled> {
+        self.writers.try_write_priority(priority)
+    }
+
+    fn close(&self) {
+        self.writers.close();
+    }
+
+    fn write_closed(&self) -> impl AccessGuard {
+        self.writers.write_closed()
+    }
+
+    fn read(&self) -> impl AccessGuard {
+        let readers = &self.readers[(self.epoch.load(Ordering::Relaxed) % 2) as usize];
+        readers.fetch_add(1, Ordering::Relaxed);
+        // Pairs with the fence in `retire`: either the writer sees this reader or the reader sees the new version.
+        fence(Ordering::SeqCst);
+        WaitFreeReadGuard { readers }
+    }
+
+    fn retire<T>(&self, _old: &Arc<T>) {
+        fence(Ordering::SeqCst);
+
+        let mut backoff = SpinThenYield::default();
+        for _ in 0..2 {
+            let parity = self.epoch.fetch_add(1, Ordering::AcqRel) % 2;
+            while self.readers[parity as usize].load(Ordering::Acquire) != 0 {
+                backoff.wait();
+            }
+        }
+    }
+}
+
+pub struct WaitFreeReadGuard<'a> {
+    readers: &'a AtomicU64,
+}
+
+impl AccessGuard for WaitFreeReadGuard<'_> {}
+
+impl Drop for WaitFreeReadGuard<'_> {
+    fn drop(&mut self) {
+        self.readers.fetch_sub(1, Ordering::Release);
+    }
+}
@@ -1,12 +1,19 @@
-use crate::access::AtomicAccessControl;
+use crate::access::cancel::{CancelToken, Cancelled};
 use crate::access::cas::CASAccessControl;
 use crate::access::lock::LockAccessControl;
+use crate::access::policy::Priority;
+use crate::access::{AccessGuard, AtomicAccessControl};
+use crate::group::AtomicGroup;
+use crate::reentrancy::{self, Held};
 use crate::sync::Arc;
-use crate::sync::{AtomicPtr, Ordering};
-use std::fmt::Debug;
-use std::sync::atomic::AtomicU64;
+use crate::sync::{AtomicBool, AtomicPtr, AtomicU64, Ordering, spin_loop};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::mem::ManuallyDrop;
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
 
-static ATOMIC_ID_GEN: AtomicU64 = AtomicU64::new(0);
+// Plain std atomic, loom ones can't live in a static.
+static ATOMIC_ID_GEN: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
 
 pub struct Atomic<T, A>
 where
@@ -15,8 +22,21 @@ where
     _id: u64,
     // Initialized refs in 1. When write happens is reduced by 1 to only in flight current reads
     current: AtomicPtr<T>,
+    // Set once under the write access, from then on `current` never changes and reads skip the control.
+    frozen: AtomicBool,
+    frozen_writes: FrozenWrites,
+    // Set before the control is closed, checked by writers under the access like `frozen`.
+    closed: AtomicBool,
+    // Set under the write access by a write closure that panicked with `PanicPolicy::Poison`.
+    poisoned: AtomicBool,
+    panic_policy: PanicPolicy,
+    // Bumped right before and right after each swap of `current`, so it is odd while a swap is in flight.
+    // Lets optimistic transactions detect the atomic changed since they read it.
+    version: AtomicU64,
     // Masks for readers, writers, version
     control: A,
+    // Shared version of the atomics that must be read at a consistent point in time.
+    group: Option<AtomicGroup>,
 }
 
 // If drop, reduce the active references to current and if zero.
@@ -33,61 +53,442 @@ where
 
 impl<T: Debug> Atomic<T, CASAccessControl> {
     pub fn new_cas(value: T, max_write_line: u16) -> Atomic<T, CASAccessControl> {
-        let raw = Arc::into_raw(Arc::new(value)) as *mut T;
-
-        Atomic {
-            _id: ATOMIC_ID_GEN.fetch_add(1, Ordering::Release),
-            current: AtomicPtr::new(raw),
-            control: CASAccessControl::new(max_write_line),
-        }
+        Atomic::new(value, CASAccessControl::new(max_write_line))
     }
 }
 
 impl<T: Debug> Atomic<T, LockAccessControl> {
     pub fn new_lock(value: T) -> Atomic<T, LockAccessControl> {
+        Atomic::new(value, LockAccessControl::default())
+    }
+}
+
+impl<T: Debug, A: AtomicAccessControl> Atomic<T, A> {
+    pub fn new(value: T, control: A) -> Self {
         let raw = Arc::into_raw(Arc::new(value)) as *mut T;
 
         Atomic {
             _id: ATOMIC_ID_GEN.fetch_add(1, Ordering::Release),
             current: AtomicPtr::new(raw),
-            control: LockAccessControl::default(),
+            frozen: AtomicBool::new(false),
+            frozen_writes: FrozenWrites::default(),
+            closed: AtomicBool::new(false),
+            poisoned: AtomicBool::new(false),
+            panic_policy: PanicPolicy::default(),
+            version: AtomicU64::new(0),
+            control,
+            group: None,
         }
     }
-}
 
-impl<T: Debug, A: AtomicAccessControl> Atomic<T, A> {
+    // Joins the group so its writes are visible to `AtomicGroup::read_all` at once or not at all.
+    pub fn in_group(mut self, group: &AtomicGroup) -> Self {
+        self.group = Some(group.clone());
+        self
+    }
+
+    pub fn with_frozen_writes(mut self, frozen_writes: FrozenWrites) -> Self {
+        self.frozen_writes = frozen_writes;
+        self
+    }
+
+    pub fn with_panic_policy(mut self, panic_policy: PanicPolicy) -> Self {
+        self.panic_policy = panic_policy;
+        self
+    }
+
+    // Makes the current value final. Waits for the write access, so writes already in flight land first and the ones
+    // still waiting fail. Irreversible, freezing again does nothing.
+    pub fn freeze(&self) {
+        if self.is_frozen() {
+            return;
+        }
+        reentrancy::check(self._id);
+        // Closed meanwhile, `close` freezes it.
+        let Ok(guard_) = self.control.try_write_priority(Priority::Normal) else {
+            return;
+        };
+        self.frozen.store(true, Ordering::Release);
+        drop(guard_);
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Acquire)
+    }
+
+    // Shuts the atomic down for good. Writes waiting for the access and later ones fail with `Closed`, the control releases
+    // its waiters at once where it can. Reads keep getting the final value, from then on without the control.
+    // Only `try_write` and `write_cancellable` return the failure. The other writes panic with it, as on a frozen atomic,
+    // unless configured with `FrozenWrites::Ignore`, which drops them instead. Transactions and `exchange_with` always panic.
+    pub fn close(&self) {
+        reentrancy::check(self._id);
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.control.close();
+        // Writers already holding the access land first, the later ones see the atomic closed.
+        let guard_ = self.control.write_closed();
+        self.frozen.store(true, Ordering::Release);
+        drop(guard_);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    // Lets writes in again after a write closure panicked with `PanicPolicy::Poison`. The value is the one it panicked on.
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Release);
+    }
+
+    pub fn group(&self) -> Option<&AtomicGroup> {
+        self.group.as_ref()
+    }
+
+    pub fn control(&self) -> &A {
+        &self.control
+    }
+
     pub fn read(&self) -> Arc<T> {
-        let _guard = self.control.read();
+        reentrancy::check(self._id);
+        // The final version is only released with the atomic itself.
+        if self.frozen.load(Ordering::Acquire) {
+            return unsafe { Self::clone_raw(self.current.load(Ordering::Acquire)) };
+        }
+        let (_guard, raw) = self.control.protect(&self.current);
+        unsafe { Self::clone_raw(raw) }
+    }
 
-        let p = self.current.load(Ordering::Acquire) as *const T;
-        unsafe {
-            let tmp = Arc::from_raw(p);
-            let out = Arc::clone(&tmp);
-            std::mem::forget(tmp);
-            out
+    // Same as `read` but also returns the version the value belongs to.
+    pub fn read_versioned(&self) -> (Arc<T>, u64) {
+        // Only retries when a writer swaps between the loads.
+        loop {
+            let version = self.version.load(Ordering::Acquire);
+            if version % 2 == 1 {
+                spin_loop();
+                continue;
+            }
+            let value = self.read();
+            if self.version.load(Ordering::Acquire) == version {
+                return (value, version);
+            }
         }
     }
 
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
     pub fn write<F>(&self, update_fn: F)
     where
         F: Fn(&T) -> T,
     {
-        let guard_ = self.control.write();
+        self.write_priority(Priority::Normal, update_fn);
+    }
+
+    // Same as `write`, with controls that support it high priority writes get in ahead of the pending normal ones.
+    pub fn write_priority<F>(&self, priority: Priority, update_fn: F)
+    where
+        F: Fn(&T) -> T,
+    {
+        // A panic caught with `PanicPolicy::KeepOld` has nothing to be reported in.
+        let old = self.replace_with_priority(priority, |current| Arc::new(update_fn(current)));
+        drop(old);
+    }
+
+    // Same as `write`, but returns the value the closure was given, the one it replaced. Stands in for reading inside the
+    // closure, where the read would wait on the write itself. With `PanicPolicy::KeepOld` a panic returns the value kept.
+    pub fn read_in_write<F>(&self, update_fn: F) -> Arc<T>
+    where
+        F: Fn(&T) -> T,
+    {
+        let Ok(guard_) = self.write_access_or_panic(Priority::Normal) else {
+            return self.read();
+        };
+        let current = unsafe { Self::clone_raw(self.current.load(Ordering::Acquire)) };
+        let replaced = self.replace_under(guard_, |current| Arc::new(update_fn(current)));
+        drop(replaced);
+        current
+    }
+
+    // Same write phase as `write` but the replaced version is handed back instead of released.
+    pub(crate) fn replace_with<F>(&self, new_fn: F) -> Result<Arc<T>, WriteError>
+    where
+        F: FnOnce(&T) -> Arc<T>,
+    {
+        self.replace_with_priority(Priority::Normal, new_fn)
+    }
+
+    // Same as `replace_with` but reports a closed or frozen atomic instead of panicking, like `try_write`.
+    pub(crate) fn try_replace_with<F>(&self, new_fn: F) -> Result<Arc<T>, WriteError>
+    where
+        F: FnOnce(&T) -> Arc<T>,
+    {
+        let guard_ = self.write_access(Priority::Normal)?;
+        self.replace_under(guard_, new_fn)
+    }
+
+    // Same as `write`, but gives up with `WriteError::Cancelled` if the token is cancelled while waiting for the write access.
+    // Fails with a `WriteError` rather than just `Cancelled`, a closed, frozen or poisoned atomic and a closure panicking
+    // under `PanicPolicy::KeepOld` turn the write away as well.
+    pub fn write_cancellable<F>(&self, token: &CancelToken, update_fn: F) -> Result<(), WriteError>
+    where
+        F: Fn(&T) -> T,
+    {
+        reentrancy::check(self._id);
+        let guard_ = self.control.write_cancellable(token).map_err(|Cancelled| {
+            if self.closed.load(Ordering::Relaxed) {
+                WriteError::Closed
+            } else {
+                WriteError::Cancelled
+            }
+        })?;
+        let guard_ = self.writable(guard_)?;
+        let old = self.replace_under(guard_, |current| Arc::new(update_fn(current)))?;
+        drop(old);
+        Ok(())
+    }
+
+    // Same as `write`, but reports a closed or frozen atomic instead of panicking.
+    pub fn try_write<F>(&self, update_fn: F) -> Result<(), WriteError>
+    where
+        F: Fn(&T) -> T,
+    {
+        let guard_ = self.write_access(Priority::Normal)?;
+        let old = self.replace_under(guard_, |current| Arc::new(update_fn(current)))?;
+        drop(old);
+        Ok(())
+    }
+
+    // Failing to get the access panics, only a frozen or closed atomic with `FrozenWrites::Ignore` and a panic caught with
+    // `PanicPolicy::KeepOld` are returned.
+    fn replace_with_priority<F>(&self, priority: Priority, new_fn: F) -> Result<Arc<T>, WriteError>
+    where
+        F: FnOnce(&T) -> Arc<T>,
+    {
+        let guard_ = self.write_access_or_panic(priority)?;
+        self.replace_under(guard_, new_fn)
+    }
 
-        let new_val = unsafe {
-            let current_arc = Arc::from_raw(self.current.load(Ordering::Acquire));
-            let v = update_fn(&current_arc);
-            std::mem::forget(current_arc);
-            v
+    fn write_access(&self, priority: Priority) -> Result<impl AccessGuard + '_, WriteError> {
+        reentrancy::check(self._id);
+        let guard_ = self
+            .control
+            .try_write_priority(priority)
+            .map_err(|Cancelled| WriteError::Closed)?;
+        self.writable(guard_)
+    }
+
+    // Write access taken, unless the atomic got frozen, closed or poisoned before. Checked under the access, all are set under it too.
+    fn writable<G: AccessGuard>(&self, guard_: G) -> Result<Held<G>, WriteError> {
+        let error = if self.closed.load(Ordering::Relaxed) {
+            WriteError::Closed
+        } else if self.frozen.load(Ordering::Relaxed) {
+            WriteError::Frozen
+        } else if self.poisoned.load(Ordering::Relaxed) {
+            WriteError::Poisoned
+        } else {
+            return Ok(Held::new(self._id, guard_));
         };
+        drop(guard_);
+        Err(error)
+    }
 
-        let new_raw = Arc::into_raw(Arc::new(new_val)) as *mut T;
-        let old_raw = self.current.swap(new_raw, Ordering::AcqRel);
+    // For the writes with nothing to report a failure in, those panic. Only a frozen or closed atomic configured with
+    // `FrozenWrites::Ignore` is reported, for the update to be dropped.
+    fn write_access_or_panic(
+        &self,
+        priority: Priority,
+    ) -> Result<impl AccessGuard + '_, WriteError> {
+        self.write_access(priority).map_err(|error| match error {
+            WriteError::Frozen | WriteError::Closed
+                if self.frozen_writes == FrozenWrites::Ignore =>
+            {
+                error
+            }
+            error => panic!("{error}"),
+        })
+    }
+
+    // The current value is only replaced once `new_fn` returns, a panic leaves it as it was whatever the policy.
+    fn replace_under<G, F>(&self, guard_: G, new_fn: F) -> Result<Arc<T>, WriteError>
+    where
+        G: AccessGuard,
+        F: FnOnce(&T) -> Arc<T>,
+    {
+        let current = unsafe { self.current_locked() };
+        let new_arc = self.run_write_fn(current, new_fn)?;
+        let old_arc = unsafe { self.swap_locked(new_arc) };
 
         drop(guard_);
+        Ok(old_arc)
+    }
+
+    // Runs a write closure as the panic policy says. Caller must hold the write access, a poisoning panic marks the atomic
+    // under it.
+    pub(crate) fn run_write_fn<F>(&self, current: &T, new_fn: F) -> Result<Arc<T>, WriteError>
+    where
+        F: FnOnce(&T) -> Arc<T>,
+    {
+        if self.panic_policy == PanicPolicy::Propagate {
+            return Ok(new_fn(current));
+        }
+        catch_unwind(AssertUnwindSafe(|| new_fn(current))).or_else(|payload| {
+            if self.panic_policy == PanicPolicy::KeepOld {
+                return Err(WriteError::Panicked);
+            }
+            self.poisoned.store(true, Ordering::Relaxed);
+            resume_unwind(payload)
+        })
+    }
 
+    // Swaps the values of both atomics at once. Both write accesses are taken in `_id` order so crossed exchanges can't deadlock,
+    // and readers of either one are held back until both pointers are swapped.
+    pub fn exchange_with<B: AtomicAccessControl>(&self, other: &Atomic<T, B>) {
+        if self._id == other._id {
+            return;
+        }
+
+        let guards_ = if self._id < other._id {
+            let self_guard = self.lock_write();
+            (self_guard, other.lock_write())
+        } else {
+            let other_guard = other.lock_write();
+            (self.lock_write(), other_guard)
+        };
+
+        let groups = match (&self.group, &other.group) {
+            (Some(self_group), Some(other_group)) if self_group.same_as(other_group) => {
+                [Some(self_group), None]
+            }
+            (self_group, other_group) => [self_group.as_ref(), other_group.as_ref()],
+        };
+        groups
+            .iter()
+            .flatten()
+            .for_each(|group| group.begin_write());
+
+        self.version.fetch_add(1, Ordering::SeqCst);
+        other.version.fetch_add(1, Ordering::SeqCst);
+        let self_raw = self.current.load(Ordering::Acquire);
+        let other_raw = other.current.swap(self_raw, Ordering::AcqRel);
+        self.current.store(other_raw, Ordering::Release);
+        self.version.fetch_add(1, Ordering::SeqCst);
+        other.version.fetch_add(1, Ordering::SeqCst);
+
+        // Each value now lives on in the other atomic, whose control knows nothing of the readers that loaded it here.
         unsafe {
-            drop(Arc::from_raw(old_raw));
+            let self_old = ManuallyDrop::new(Arc::from_raw(self_raw));
+            let other_old = ManuallyDrop::new(Arc::from_raw(other_raw));
+            self.control.retire(&self_old);
+            other.control.retire(&other_old);
         }
+
+        groups.iter().flatten().for_each(|group| group.end_write());
+
+        drop(guards_);
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self._id
+    }
+
+    pub(crate) fn lock_write(&self) -> impl AccessGuard + '_ {
+        self.write_access(Priority::Normal)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    // Write access taken only for the value not to change while held. None once closed, the value is final then.
+    pub(crate) fn lock_unchanged(&self) -> Option<impl AccessGuard + '_> {
+        reentrancy::check(self._id);
+        let guard_ = self.control.try_write_priority(Priority::Normal).ok()?;
+        Some(Held::new(self._id, guard_))
+    }
+
+    // Caller must hold the access `raw` was loaded under, so it can't be released meanwhile.
+    unsafe fn clone_raw(raw: *mut T) -> Arc<T> {
+        let p = raw as *const T;
+        unsafe {
+            let tmp = Arc::from_raw(p);
+            let out = Arc::clone(&tmp);
+            std::mem::forget(tmp);
+            out
+        }
+    }
+
+    // Caller must hold the write access, so the current version can't be replaced meanwhile.
+    pub(crate) unsafe fn current_locked(&self) -> &T {
+        unsafe { &*self.current.load(Ordering::Acquire) }
     }
+
+    // Caller must hold the write access. The group, if any, is notified around the swap itself.
+    pub(crate) unsafe fn swap_locked(&self, new_arc: Arc<T>) -> Arc<T> {
+        let new_raw = Arc::into_raw(new_arc) as *mut T;
+
+        if let Some(group) = &self.group {
+            group.begin_write();
+        }
+        // Versions are bumped around the swap: who sees the old version can't have missed the new value, an odd one means a swap is in flight.
+        self.version.fetch_add(1, Ordering::SeqCst);
+        let old_raw = self.current.swap(new_raw, Ordering::SeqCst);
+        self.version.fetch_add(1, Ordering::SeqCst);
+        if let Some(group) = &self.group {
+            group.end_write();
+        }
+
+        let old_arc = unsafe { Arc::from_raw(old_raw) };
+        self.control.retire(&old_arc);
+        old_arc
+    }
+}
+
+// What `write` and the other writes with nothing to return an error in do to a frozen or closed atomic. `try_write` and
+// `write_cancellable` always return `WriteError::Frozen` or `WriteError::Closed`, transactions and `exchange_with` always
+// panic, they can't commit only part of their writes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrozenWrites {
+    #[default]
+    Panic,
+    // The update is dropped, `read_in_write` returns the final value.
+    Ignore,
 }
+
+// What happens when a write closure panics. The value it panicked on stays current and the control is released either way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    // The panic unwinds to the writer, later writes go on as if nothing happened.
+    #[default]
+    Propagate,
+    // The panic unwinds to the writer, later writes fail with `WriteError::Poisoned` until `clear_poison`.
+    Poison,
+    // The panic is caught, `try_write` and `write_cancellable` return `WriteError::Panicked`. A transaction commits nothing.
+    KeepOld,
+}
+
+// Why a write didn't happen. Writes with nothing to return it in panic with it instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteError {
+    Cancelled,
+    Frozen,
+    Closed,
+    Poisoned,
+    Panicked,
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::Cancelled => Display::fmt(&Cancelled, f),
+            WriteError::Frozen => f.write_str("write to a frozen atomic"),
+            WriteError::Closed => f.write_str("write to a closed atomic"),
+            WriteError::Poisoned => f.write_str("write to a poisoned atomic"),
+            WriteError::Panicked => f.write_str("write closure panicked, the old value is kept"),
+        }
+    }
+}
+
+impl Error for WriteError {}
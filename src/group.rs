@@ -0,0 +1,126 @@
+use crate::access::AtomicAccessControl;
+use crate::access::backoff::{Backoff, SpinThenYield};
+use crate::atomic::Atomic;
+use crate::sync::{Arc, AtomicU64, Ordering, fence};
+use crossbeam_utils::CachePadded;
+use std::fmt::Debug;
+
+const ACTIVE_WRITERS_MASK: u64 = 0x0000_0000_FFFF_FFFF;
+const VERSION_UNIT: u64 = 1 << 32;
+
+// Shared version for a set of atomics. Writes to any member are bracketed by `begin_write` / `end_write` around the pointer swap only,
+// so the readers validating the version retry at most while a swap is in flight, never while the new value is being computed.
+#[derive(Clone, Default)]
+pub struct AtomicGroup {
+    // 0-32 bits hold members currently swapping his pointer.
+    // 32-64 bits hold the group version, bumped each time a swap finishes.
+    state: Arc<CachePadded<AtomicU64>>,
+}
+
+impl AtomicGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains<T, A: AtomicAccessControl>(&self, atomic: &Atomic<T, A>) -> bool
+    where
+        T: Debug,
+    {
+        atomic.group().is_some_and(|group| group.same_as(self))
+    }
+
+    pub(crate) fn same_as(&self, other: &AtomicGroup) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+
+    // Reads every member from the same point in time. Members must belong to this group.
+    pub fn read_all<M: GroupMembers>(&self, members: M) -> M::Snapshot {
+        assert!(
+            members.all_in(self),
+            "Every atomic read together must belong to the group"
+        );
+        self.read_with(|| members.read_members())
+    }
+
+    // Same as `read_all` using the group of the members themselves. Used by `snapshot!`.
+    pub fn snapshot<M: GroupMembers>(members: M) -> M::Snapshot {
+        let group = members
+            .group()
+            .expect("Atomics read together must belong to a group")
+            .clone();
+        group.read_all(members)
+    }
+
+    // Retries `read_fn` until no member was swapped while it ran.
+    pub fn read_with<R, F>(&self, mut read_fn: F) -> R
+    where
+        F: FnMut() -> R,
+    {
+        let mut backoff = SpinThenYield::default();
+        loop {
+            let before = self.state.load(Ordering::Acquire);
+            if before & ACTIVE_WRITERS_MASK == 0 {
+                let out = read_fn();
+
+                fence(Ordering::Acquire);
+                if self.state.load(Ordering::Relaxed) == before {
+                    return out;
+                }
+            }
+
+            backoff.wait();
+        }
+    }
+
+    pub(crate) fn begin_write(&self) {
+        self.state.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
+    }
+
+    pub(crate) fn end_write(&self) {
+        self.state.fetch_add(VERSION_UNIT - 1, Ordering::Release);
+    }
+}
+
+pub trait GroupMembers {
+    type Snapshot;
+
+    fn group(&self) -> Option<&AtomicGroup>;
+    fn all_in(&self, group: &AtomicGroup) -> bool;
+    fn read_members(&self) -> Self::Snapshot;
+}
+
+macro_rules! impl_group_members {
+    ($first:tt $(, $idx:tt)*; $($t:ident $a:ident),+) => {
+        impl<'a, $($t: Debug, $a: AtomicAccessControl),+> GroupMembers for ($(&'a Atomic<$t, $a>,)+) {
+            type Snapshot = ($(Arc<$t>,)+);
+
+            fn group(&self) -> Option<&AtomicGroup> {
+                self.$first.group()
+            }
+
+            fn all_in(&self, group: &AtomicGroup) -> bool {
+                group.contains(self.$first) $(&& group.contains(self.$idx))*
+            }
+
+            fn read_members(&self) -> Self::Snapshot {
+                (self.$first.read(), $(self.$idx.read(),)*)
+            }
+        }
+    };
+}
+
+impl_group_members!(0; T0 A0);
+impl_group_members!(0, 1; T0 A0, T1 A1);
+impl_group_members!(0, 1, 2; T0 A0, T1 A1, T2 A2);
+impl_group_members!(0, 1, 2, 3; T0 A0, T1 A1, T2 A2, T3 A3);
+impl_group_members!(0, 1, 2, 3, 4; T0 A0, T1 A1, T2 A2, T3 A3, T4 A4);
+impl_group_members!(0, 1, 2, 3, 4, 5; T0 A0, T1 A1, T2 A2, T3 A3, T4 A4, T5 A5);
+
+// Reads the given grouped atomics from a consistent point in time, e.g. `let (routes, acl) = snapshot!(routes, acl);`
+#[macro_export]
+macro_rules! snapshot {
+    ($($atomic:expr),+ $(,)?) => {
+        $crate::group::AtomicGroup::snapshot(($(&$atomic,)+))
+    };
+}
@@ -1,6 +1,11 @@
 pub mod access;
 pub mod atomic;
+pub mod group;
+mod reentrancy;
+pub mod seq;
 mod sync;
+pub mod transaction;
+pub mod undo;
 
 #[cfg(any(test, feature = "benches"))]
 #[doc(hidden)]
@@ -0,0 +1,68 @@
+use crate::access::AccessGuard;
+#[cfg(debug_assertions)]
+use std::cell::RefCell;
+
+// Ids of the atomics whose write access this thread holds. A read or write of one of them from inside its own write
+// closure would wait on the thread itself, debug builds panic instead. Release builds keep nothing.
+#[cfg(all(debug_assertions, not(loom)))]
+thread_local! {
+    static HELD: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+// Loom runs its threads on one OS thread, they need its own thread locals. Those take no const initializer.
+#[cfg(all(debug_assertions, loom))]
+loom::thread_local! {
+    static HELD: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
+
+// Called before taking any access to the atomic.
+#[cfg(debug_assertions)]
+pub(crate) fn check(id: u64) {
+    if HELD.with(|held| held.borrow().contains(&id)) {
+        panic!(
+            "atomic accessed from inside its own write closure, it would deadlock. Use `read_in_write` to get the value being replaced"
+        );
+    }
+}
+
+#[cfg(not(debug_assertions))]
+pub(crate) fn check(_id: u64) {}
+
+// Write access of the atomic, known to be held by this thread until dropped.
+pub(crate) struct Held<G: AccessGuard> {
+    _guard: G,
+    #[cfg(debug_assertions)]
+    id: u64,
+}
+
+impl<G: AccessGuard> Held<G> {
+    #[cfg(debug_assertions)]
+    pub(crate) fn new(id: u64, guard: G) -> Self {
+        HELD.with(|held| held.borrow_mut().push(id));
+        Self { _guard: guard, id }
+    }
+
+    #[cfg(not(debug_assertions))]
+    pub(crate) fn new(_id: u64, guard: G) -> Self {
+        Self { _guard: guard }
+    }
+}
+
+impl<G: AccessGuard> AccessGuard for Held<G> {}
+
+// Forgotten before the guard is released, the thread no longer waits on itself once it is.
+#[cfg(debug_assertions)]
+impl<G: AccessGuard> Drop for Held<G> {
+    fn drop(&mut self) {
+        HELD.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(idx) = held.iter().rposition(|id| *id == self.id) {
+                held.swap_remove(idx);
+            }
+            // A thread holding no access keeps nothing allocated.
+            if held.is_empty() {
+                *held = Vec::new();
+            }
+        });
+    }
+}
@@ -0,0 +1,86 @@
+use crate::access::AtomicAccessControl;
+use crate::access::cas::CASAccessControl;
+use crate::sync::{AtomicU64, Ordering, fence, spin_loop};
+use crossbeam_utils::CachePadded;
+use std::cell::UnsafeCell;
+use std::fmt::Debug;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+// Seqlock for small `Copy` values stored inline, no allocation per write. Readers never enter the access control, they
+// copy the value optimistically and retry if the sequence moved meanwhile. Writers are serialized by the control's
+// write phases and keep the sequence odd while the value is being overwritten.
+pub struct AtomicSeq<T: Copy, A = CASAccessControl>
+where
+    A: AtomicAccessControl,
+{
+    seq: CachePadded<AtomicU64>,
+    value: UnsafeCell<T>,
+    control: A,
+}
+
+// Readers only ever copy the value out, never reference it.
+unsafe impl<T: Copy + Send, A: AtomicAccessControl> Sync for AtomicSeq<T, A> {}
+
+impl<T: Copy + Debug> AtomicSeq<T, CASAccessControl> {
+    pub fn new_cas(value: T, max_write_line: u16) -> Self {
+        AtomicSeq::new(value, CASAccessControl::new(max_write_line))
+    }
+}
+
+impl<T: Copy + Debug, A: AtomicAccessControl> AtomicSeq<T, A> {
+    pub fn new(value: T, control: A) -> Self {
+        AtomicSeq {
+            seq: Default::default(),
+            value: UnsafeCell::new(value),
+            control,
+        }
+    }
+
+    pub fn control(&self) -> &A {
+        &self.control
+    }
+
+    pub fn read(&self) -> T {
+        self.read_versioned().0
+    }
+
+    // Same as `read` but also returns the version the value belongs to, bumped by two on each write.
+    pub fn read_versioned(&self) -> (T, u64) {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq % 2 == 1 {
+                spin_loop();
+                continue;
+            }
+            // May race a writer and come out torn, it stays uninit until the sequence proves it wasn't.
+            let value = unsafe { ptr::read_volatile(self.value.get() as *const MaybeUninit<T>) };
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == seq {
+                return (unsafe { value.assume_init() }, seq);
+            }
+        }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.seq.load(Ordering::Acquire)
+    }
+
+    pub fn write<F>(&self, update_fn: F)
+    where
+        F: Fn(&T) -> T,
+    {
+        let guard_ = self.control.write();
+
+        // Writers are exclusive, nobody else stores meanwhile.
+        let new = update_fn(unsafe { &*self.value.get() });
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq + 1, Ordering::Relaxed);
+        // Readers seeing any byte of the new value also see the odd sequence.
+        fence(Ordering::Release);
+        unsafe { ptr::write_volatile(self.value.get(), new) };
+        self.seq.store(seq + 2, Ordering::Release);
+
+        drop(guard_);
+    }
+}
@@ -1,14 +1,16 @@
 #[cfg(not(loom))]
-pub(crate) use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+pub(crate) use std::sync::atomic::{
+    AtomicBool, AtomicPtr, AtomicU8, AtomicU16, AtomicU32, AtomicU64, Ordering, fence,
+};
 
 #[cfg(not(loom))]
 pub(crate) use std::sync::Arc;
 
 #[cfg(not(loom))]
-pub(crate) type Contender = crossbeam_utils::Backoff;
+pub(crate) use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-#[cfg(not(loom))]
-pub(crate) use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(all(not(loom), not(target_os = "linux")))]
+pub(crate) use std::sync::Condvar;
 
 #[cfg(loom)]
 pub(crate) use loom::sync::atomic::fence;
@@ -16,32 +18,41 @@ pub(crate) use loom::sync::atomic::fence;
 #[cfg(loom)]
 pub(crate) use loom::alloc::Layout;
 #[cfg(loom)]
-pub(crate) use loom::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
-#[cfg(loom)]
-pub(crate) type Contender = CustomBackoff;
+pub(crate) use loom::sync::{
+    Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
 
 #[cfg(loom)]
-pub(crate) use loom::sync::atomic::{AtomicPtr, AtomicU16, AtomicU64, Ordering};
+pub(crate) use loom::sync::atomic::{
+    AtomicBool, AtomicPtr, AtomicU8, AtomicU16, AtomicU32, AtomicU64, Ordering,
+};
 
 #[cfg(loom)]
 pub(crate) use loom::sync::Arc;
-#[cfg(loom)]
-pub(crate) struct CustomBackoff;
 
 #[cfg(loom)]
 pub(crate) use loom::alloc::{alloc, dealloc};
 
+#[cfg(not(loom))]
+pub(crate) use std::hint::spin_loop;
+#[cfg(not(loom))]
+pub(crate) use std::thread::{park_timeout, sleep, yield_now};
+
+// Loom can't model time, every kind of waiting is a yield to let other threads progress.
 #[cfg(loom)]
-impl CustomBackoff {
-    pub fn new() -> Self {
-        Self {}
-    }
+pub(crate) use loom::thread::yield_now;
 
-    pub fn is_completed(&self) -> bool {
-        true
-    }
+#[cfg(loom)]
+pub(crate) fn spin_loop() {
+    loom::thread::yield_now();
+}
+
+#[cfg(loom)]
+pub(crate) fn park_timeout(_timeout: std::time::Duration) {
+    loom::thread::yield_now();
+}
 
-    pub fn snooze(&self) {
-        loom::thread::yield_now();
-    }
+#[cfg(loom)]
+pub(crate) fn sleep(_duration: std::time::Duration) {
+    loom::thread::yield_now();
 }
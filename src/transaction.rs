@@ -0,0 +1,276 @@
+use crate::access::backoff::{Backoff, SpinThenYield};
+use crate::access::{AccessGuard, AtomicAccessControl};
+use crate::atomic::{Atomic, WriteError};
+use crate::group::AtomicGroup;
+use crate::sync::Arc;
+use std::fmt::Debug;
+
+// Stages writes over several atomics and commits them together once `tx_fn` returns:
+// the write access of every participant is acquired in `_id` order (deadlock free), all new values are computed, all pointers are swapped and only then the accesses are released.
+// A panicking `update_fn` commits nothing, the panic policy of its atomic applies as for a single write.
+// Readers of any participant are held back until the whole commit is done, and grouped participants are seen by `snapshot!` all or none.
+pub fn transaction<'a, R, F>(tx_fn: F) -> R
+where
+    F: FnOnce(&mut Transaction<'a>) -> R,
+{
+    let mut tx = Transaction { writes: Vec::new() };
+    let out = tx_fn(&mut tx);
+    tx.commit();
+    out
+}
+
+pub struct Transaction<'a> {
+    writes: Vec<Box<dyn StagedWrite<'a> + 'a>>,
+}
+
+impl<'a> Transaction<'a> {
+    // Writes to the same atomic are applied in staging order, each one over the result of the previous.
+    pub fn write<T, A, F>(&mut self, atomic: &'a Atomic<T, A>, update_fn: F)
+    where
+        T: Debug + 'a,
+        A: AtomicAccessControl + 'a,
+        F: FnOnce(&T) -> T + 'a,
+    {
+        self.writes.push(Box::new(Staged {
+            atomic,
+            update_fn: Some(update_fn),
+            new: None,
+            replaced: None,
+        }));
+    }
+
+    fn commit(self) {
+        commit_staged(self.writes, Vec::new());
+    }
+}
+
+// Stages writes computed from a versioned view of several atomics, with no access held, and commits them only if nothing read changed meanwhile.
+// On conflict the whole `tx_fn` runs again, so it must be free of side effects and may see values that will never be committed together.
+// The write access of every atomic read or written is acquired in `_id` order before validating, so none can change until the
+// swaps are done and two transactions each writing what the other read can't both commit. Readers are held back during the commit alone.
+pub fn optimistic<'a, R, F>(tx_fn: F) -> R
+where
+    F: Fn(&mut OptimisticTransaction<'a>) -> R,
+{
+    let mut backoff = SpinThenYield::default();
+    loop {
+        let mut tx = OptimisticTransaction {
+            reads: Vec::new(),
+            writes: Vec::new(),
+        };
+        let out = tx_fn(&mut tx);
+
+        if commit_staged(tx.writes, tx.reads) {
+            return out;
+        }
+
+        backoff.wait();
+    }
+}
+
+pub struct OptimisticTransaction<'a> {
+    reads: Vec<ReadStamp<'a>>,
+    writes: Vec<Box<dyn StagedWrite<'a> + 'a>>,
+}
+
+impl<'a> OptimisticTransaction<'a> {
+    // Always returns the committed value, staged writes are not visible until the commit.
+    pub fn read<T, A>(&mut self, atomic: &'a Atomic<T, A>) -> Arc<T>
+    where
+        T: Debug + 'a,
+        A: AtomicAccessControl + 'a,
+    {
+        let (value, version) = atomic.read_versioned();
+        self.reads.push(ReadStamp { atomic, version });
+        value
+    }
+
+    pub fn write<T, A>(&mut self, atomic: &'a Atomic<T, A>, value: T)
+    where
+        T: Debug + 'a,
+        A: AtomicAccessControl + 'a,
+    {
+        self.writes.push(Box::new(Staged {
+            atomic,
+            update_fn: Some(move |_: &T| value),
+            new: None,
+            replaced: None,
+        }));
+    }
+}
+
+struct ReadStamp<'a> {
+    atomic: &'a dyn Versioned,
+    version: u64,
+}
+
+impl<'a> ReadStamp<'a> {
+    fn is_current(&self) -> bool {
+        self.atomic.version() == self.version
+    }
+
+    fn id(&self) -> u64 {
+        self.atomic.id()
+    }
+
+    fn hold(&self) -> Option<Box<dyn AccessGuard + 'a>> {
+        self.atomic.hold()
+    }
+}
+
+trait Versioned {
+    fn version(&self) -> u64;
+    fn id(&self) -> u64;
+    fn hold(&self) -> Option<Box<dyn AccessGuard + '_>>;
+}
+
+impl<T: Debug, A: AtomicAccessControl> Versioned for Atomic<T, A> {
+    fn version(&self) -> u64 {
+        Atomic::version(self)
+    }
+
+    fn id(&self) -> u64 {
+        Atomic::id(self)
+    }
+
+    fn hold(&self) -> Option<Box<dyn AccessGuard + '_>> {
+        self.lock_unchanged()
+            .map(|guard_| Box::new(guard_) as Box<dyn AccessGuard>)
+    }
+}
+
+// Acquires the write access of every staged and read atomic in `_id` order and, if the reads are all still current, swaps
+// all of them before releasing. Returns false only if a read was not.
+fn commit_staged<'a>(
+    mut writes: Vec<Box<dyn StagedWrite<'a> + 'a>>,
+    mut reads: Vec<ReadStamp<'a>>,
+) -> bool {
+    // Stable, so writes to the same atomic keep their staging order.
+    writes.sort_by_key(|write| write.id());
+    reads.sort_by_key(ReadStamp::id);
+    // Only the atomics read and not written are held apart, each once.
+    let mut held = reads.iter().collect::<Vec<_>>();
+    held.dedup_by_key(|read| read.id());
+    held.retain(|read| writes.iter().all(|write| write.id() != read.id()));
+
+    let mut guards: Vec<Box<dyn AccessGuard + 'a>> = Vec::with_capacity(writes.len() + held.len());
+    let mut groups: Vec<AtomicGroup> = Vec::new();
+    let mut held = held.into_iter().peekable();
+    let mut last_id = None;
+    for write in &writes {
+        if last_id == Some(write.id()) {
+            continue;
+        }
+        last_id = Some(write.id());
+        while let Some(read) = held.next_if(|read| read.id() < write.id()) {
+            guards.extend(read.hold());
+        }
+        guards.push(write.lock());
+
+        if let Some(group) = write.group()
+            && !groups.iter().any(|known| known.same_as(group))
+        {
+            groups.push(group.clone());
+        }
+    }
+    held.for_each(|read| guards.extend(read.hold()));
+
+    if !reads.iter().all(ReadStamp::is_current) {
+        return false;
+    }
+
+    // Every new value is computed before anything is swapped, a panicking `update_fn` leaves all participants untouched.
+    // Caught by `PanicPolicy::KeepOld`, the whole commit is dropped, not retried.
+    for idx in 0..writes.len() {
+        let (computed, pending) = writes.split_at_mut(idx);
+        let write = &mut pending[0];
+        let previous = computed
+            .last()
+            .filter(|previous| previous.id() == write.id())
+            .map(|previous| previous.staged());
+        if write.compute(previous).is_err() {
+            return true;
+        }
+    }
+
+    // Only the last write to each atomic is swapped in, the ones before are steps towards it.
+    groups.iter().for_each(AtomicGroup::begin_write);
+    for idx in 0..writes.len() {
+        let id = writes[idx].id();
+        if writes.get(idx + 1).is_none_or(|next| next.id() != id) {
+            writes[idx].apply();
+        }
+    }
+    groups.iter().for_each(AtomicGroup::end_write);
+
+    drop(guards);
+    // Replaced versions are released once no access is held.
+    drop(writes);
+    true
+}
+
+trait StagedWrite<'a> {
+    fn id(&self) -> u64;
+    fn group(&self) -> Option<&AtomicGroup>;
+    fn lock(&self) -> Box<dyn AccessGuard + 'a>;
+    // Caller must hold the write access of the atomic. `previous` is the staged value of the write to the same atomic
+    // just before this one, if any. Runs `update_fn` as the panic policy of the atomic says.
+    fn compute(&mut self, previous: Option<*const ()>) -> Result<(), WriteError>;
+    // Points to the computed value, a `T` of the atomic.
+    fn staged(&self) -> *const ();
+    // Caller must hold the write access of the atomic.
+    fn apply(&mut self);
+}
+
+struct Staged<'a, T, A, F>
+where
+    A: AtomicAccessControl,
+{
+    atomic: &'a Atomic<T, A>,
+    update_fn: Option<F>,
+    new: Option<Arc<T>>,
+    replaced: Option<Arc<T>>,
+}
+
+impl<'a, T, A, F> StagedWrite<'a> for Staged<'a, T, A, F>
+where
+    T: Debug + 'a,
+    A: AtomicAccessControl + 'a,
+    F: FnOnce(&T) -> T,
+{
+    fn id(&self) -> u64 {
+        self.atomic.id()
+    }
+
+    fn group(&self) -> Option<&AtomicGroup> {
+        self.atomic.group()
+    }
+
+    fn lock(&self) -> Box<dyn AccessGuard + 'a> {
+        Box::new(self.atomic.lock_write())
+    }
+
+    fn compute(&mut self, previous: Option<*const ()>) -> Result<(), WriteError> {
+        let update_fn = self.update_fn.take().expect("Always computed once");
+        // Same id, same atomic: the previous write staged a `T` as well.
+        let current = match previous {
+            Some(previous) => unsafe { &*previous.cast::<T>() },
+            None => unsafe { self.atomic.current_locked() },
+        };
+        let new_arc = self
+            .atomic
+            .run_write_fn(current, |current| Arc::new(update_fn(current)))?;
+        self.new = Some(new_arc);
+        Ok(())
+    }
+
+    fn staged(&self) -> *const () {
+        let new = self.new.as_deref().expect("Always computed before");
+        (new as *const T).cast()
+    }
+
+    fn apply(&mut self) {
+        let new_arc = self.new.take().expect("Always computed before applied");
+        self.replaced = Some(unsafe { self.atomic.swap_locked(new_arc) });
+    }
+}
@@ -0,0 +1,138 @@
+use crate::access::AtomicAccessControl;
+use crate::atomic::Atomic;
+use crate::sync::{Arc, Mutex, MutexGuard};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::sync::PoisonError;
+
+struct History<T> {
+    // Replaced versions, most recent at the back. Oldest are evicted once `depth` is reached.
+    undo: VecDeque<Arc<T>>,
+    // Versions taken back by `undo`, most recent at the back. Any fresh write invalidates them.
+    redo: Vec<Arc<T>>,
+    depth: usize,
+}
+
+impl<T> History<T> {
+    fn record(&mut self, replaced: Arc<T>) {
+        if self.undo.len() == self.depth {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(replaced);
+    }
+}
+
+// Keeps every committed version of the inner Atomic to move back and forth between them.
+// Writes, undo and redo are serialized by the history lock so the recorded order always matches the commit order. Reads never touch it.
+pub struct UndoableAtomic<T, A>
+where
+    A: AtomicAccessControl,
+{
+    atomic: Atomic<T, A>,
+    history: Mutex<History<T>>,
+}
+
+impl<T: Debug, A: AtomicAccessControl> UndoableAtomic<T, A> {
+    pub fn new(atomic: Atomic<T, A>, depth: usize) -> Self {
+        assert!(depth > 0);
+        Self {
+            atomic,
+            history: Mutex::new(History {
+                undo: VecDeque::with_capacity(depth),
+                redo: Vec::new(),
+                depth,
+            }),
+        }
+    }
+
+    pub fn read(&self) -> Arc<T> {
+        self.atomic.read()
+    }
+
+    pub fn write<F>(&self, update_fn: F)
+    where
+        F: Fn(&T) -> T,
+    {
+        let mut history = self.history();
+        // Nothing was replaced if the closure panicked and the atomic kept the old value.
+        let Ok(replaced) = self
+            .atomic
+            .replace_with(|current| Arc::new(update_fn(current)))
+        else {
+            return;
+        };
+
+        history.record(replaced);
+        history.redo.clear();
+    }
+
+    // Restores the version replaced by the last write or redo. Returns false if there is nothing to undo, or the atomic
+    // can't be written anymore: frozen, closed or poisoned. The version then stays in the history.
+    pub fn undo(&self) -> bool {
+        let mut history = self.history();
+        let Some(previous) = history.undo.pop_back() else {
+            return false;
+        };
+
+        match self.atomic.try_replace_with(|_| previous.clone()) {
+            Ok(replaced) => {
+                history.redo.push(replaced);
+                true
+            }
+            Err(_) => {
+                history.undo.push_back(previous);
+                false
+            }
+        }
+    }
+
+    // Restores the version taken back by the last undo. Returns false if there is nothing to redo, or the atomic can't be
+    // written anymore. The version then stays in the history.
+    pub fn redo(&self) -> bool {
+        let mut history = self.history();
+        let Some(next) = history.redo.pop() else {
+            return false;
+        };
+
+        match self.atomic.try_replace_with(|_| next.clone()) {
+            Ok(replaced) => {
+                history.record(replaced);
+                true
+            }
+            Err(_) => {
+                history.redo.push(next);
+                false
+            }
+        }
+    }
+
+    // Same as `Atomic::freeze`, undo and redo fail from then on.
+    pub fn freeze(&self) {
+        self.atomic.freeze();
+    }
+
+    // Same as `Atomic::close`.
+    pub fn close(&self) {
+        self.atomic.close();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history().undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history().redo.is_empty()
+    }
+
+    // Drops every recorded version. The current one is kept.
+    pub fn clear_history(&self) {
+        let mut history = self.history();
+        history.undo.clear();
+        history.redo.clear();
+    }
+
+    fn history(&self) -> MutexGuard<'_, History<T>> {
+        // A panicking update function leaves the history untouched, so it can still be used.
+        self.history.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
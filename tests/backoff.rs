@@ -0,0 +1,80 @@
+#![cfg(not(loom))]
+
+use lib::access::backoff::{Backoff, Park, Sleep, Spin, SpinThenPark, SpinThenYield};
+use lib::access::cas::CASAccessControl;
+use lib::atomic::Atomic;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const READERS: usize = 4;
+const WRITERS: usize = 4;
+const WORKER_WRITES: usize = 500;
+
+#[test]
+fn test_spin_backoff() {
+    perform(Spin::default());
+}
+
+#[test]
+fn test_spin_then_yield_backoff() {
+    perform(SpinThenYield::default());
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_backoff_strategy() {
+    let mut backoff = lib::access::cas::BackOffStrategy::default();
+    (0..16).for_each(|_| backoff.wait());
+    perform(backoff);
+}
+
+#[test]
+fn test_spin_then_park_backoff() {
+    perform(SpinThenPark::new(4, Duration::from_micros(10)));
+}
+
+#[test]
+fn test_park_backoff() {
+    perform(Park::default());
+}
+
+#[test]
+fn test_park_without_spinning_backoff() {
+    perform(Park::new(0));
+}
+
+#[test]
+fn test_sleep_backoff() {
+    perform(Sleep::new(Duration::from_micros(10)));
+}
+
+fn perform<B: Backoff + 'static>(backoff: B) {
+    let target = Arc::new(Atomic::new(
+        0usize,
+        CASAccessControl::new(2).with_backoff(backoff),
+    ));
+
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| {
+            let target = target.clone();
+            thread::spawn(move || {
+                while *target.read() != WRITERS * WORKER_WRITES {
+                    thread::yield_now();
+                }
+            })
+        })
+        .collect();
+    let writers: Vec<_> = (0..WRITERS)
+        .map(|_| {
+            let target = target.clone();
+            thread::spawn(move || (0..WORKER_WRITES).for_each(|_| target.write(|val| *val + 1)))
+        })
+        .collect();
+
+    writers
+        .into_iter()
+        .chain(readers)
+        .for_each(|worker| worker.join().expect(""));
+    assert_eq!(WRITERS * WORKER_WRITES, *target.read());
+}
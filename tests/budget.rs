@@ -0,0 +1,155 @@
+mod common;
+
+#[cfg(loom)]
+use lib::access::backoff::Park;
+use lib::access::cas::CASAccessControl;
+use lib::atomic::Atomic;
+use std::time::Duration;
+
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(not(loom))]
+use std::sync::{Arc, Barrier};
+#[cfg(not(loom))]
+use std::thread;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(not(loom))]
+fn budgeted_atomic(max_write_line: u16, budget: Duration) -> Arc<Atomic<usize, CASAccessControl>> {
+    Arc::new(Atomic::new(
+        0,
+        CASAccessControl::new(max_write_line).with_write_budget(budget),
+    ))
+}
+
+#[cfg(loom)]
+fn parked_budgeted_atomic(max_write_line: u16) -> Arc<Atomic<usize, CASAccessControl<Park>>> {
+    let control = CASAccessControl::new(max_write_line).with_write_budget(Duration::ZERO);
+    Arc::new(Atomic::new(0, common::parked(control)))
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_exhausted_phase_closes_early() {
+    common::model(|| {
+        let target = parked_budgeted_atomic(3);
+
+        let writers: Vec<_> = (0..2)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || target.write(|val| *val + 1))
+            })
+            .collect();
+        target.write(|val| *val + 1);
+
+        writers
+            .into_iter()
+            .for_each(|writer| writer.join().expect(""));
+        assert_eq!(3, *target.read());
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_reader_after_closed_phase() {
+    common::model(|| {
+        let target = parked_budgeted_atomic(2);
+
+        let writer = {
+            let target = target.clone();
+            thread::spawn(move || target.write(|val| *val + 1))
+        };
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 2))
+        };
+        target.write(|val| *val + 1);
+
+        writer.join().expect("");
+        reader.join().expect("");
+        assert_eq!(2, *target.read());
+    });
+}
+
+// The writers piled up behind a blocked write all hold a turn of the next phase. Its budget runs out during the first
+// of them, the others go back and let the reader in.
+#[cfg(not(loom))]
+#[test]
+fn test_reserved_turns_yield_to_reader() {
+    let target = budgeted_atomic(8, Duration::from_millis(1));
+    let blocking = Arc::new(Barrier::new(2));
+    let started = Arc::new(AtomicUsize::new(0));
+
+    let blocker = {
+        let (target, blocking) = (target.clone(), blocking.clone());
+        thread::spawn(move || {
+            target.write(|val| {
+                blocking.wait();
+                blocking.wait();
+                *val + 1
+            })
+        })
+    };
+    blocking.wait();
+    let writers: Vec<_> = (0..7)
+        .map(|_| {
+            let (target, started) = (target.clone(), started.clone());
+            thread::spawn(move || {
+                target.write(|val| {
+                    started.fetch_add(1, Ordering::Relaxed);
+                    thread::sleep(Duration::from_millis(20));
+                    *val + 1
+                })
+            })
+        })
+        .collect();
+    while target.control().pending_writers() < 7 {
+        thread::yield_now();
+    }
+    blocking.wait();
+    while started.load(Ordering::Relaxed) == 0 {
+        thread::yield_now();
+    }
+
+    assert!(*target.read() <= 2);
+    writers
+        .into_iter()
+        .chain([blocker])
+        .for_each(|writer| writer.join().expect(""));
+    assert_eq!(8, *target.read());
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_budgeted_writes(max_write_line in 1u16..8, budget in 0u64..200, sleep in proptest::bool::ANY, readers in 0usize..4, writers in 1usize..4, writes in 1usize..32) {
+        let target = budgeted_atomic(max_write_line, Duration::from_micros(budget));
+
+        let written = common::run_load(
+            &target,
+            readers,
+            writers,
+            writes,
+            move |target, _, _| {
+                target.write(|val| {
+                    if sleep {
+                        thread::sleep(Duration::from_micros(50));
+                    }
+                    *val + 1
+                });
+                true
+            },
+            || (),
+        );
+        assert_eq!(writers * writes, written);
+        // Every turn given back when a phase closed early was taken again later.
+        assert_eq!(0, target.control().pending_writers());
+    }
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use lib::access::cancel::CancelToken;
+use lib::access::cas::CASAccessControl;
+use lib::atomic::Atomic;
+
+#[cfg(not(loom))]
+use lib::access::AtomicAccessControl;
+#[cfg(not(loom))]
+use lib::access::lock::LockAccessControl;
+#[cfg(not(loom))]
+use lib::atomic::WriteError;
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::Arc;
+#[cfg(not(loom))]
+use std::thread;
+#[cfg(not(loom))]
+use std::time::Duration;
+
+#[cfg(loom)]
+use lib::access::backoff::Park;
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(loom)]
+fn parked_control(max_write_line: u16, queued: bool) -> CASAccessControl<Park> {
+    let control = CASAccessControl::new(max_write_line);
+    let control = if queued {
+        control.with_queued_writers()
+    } else {
+        control
+    };
+    common::parked(control)
+}
+
+#[cfg(loom)]
+fn cancelled_writer_hands_off(queued: bool) {
+    common::model(move || {
+        let target = Arc::new(Atomic::new(0, parked_control(2, queued)));
+        let token = CancelToken::new();
+
+        let cancellable = {
+            let target = target.clone();
+            let token = token.clone();
+            thread::spawn(move || target.write_cancellable(&token, |val| *val + 1))
+        };
+        let writer = {
+            let target = target.clone();
+            thread::spawn(move || target.write(|val| *val + 1))
+        };
+        token.cancel();
+
+        let written = cancellable.join().expect("").map_or(0, |_| 1);
+        writer.join().expect("");
+        assert_eq!(1 + written, *target.read());
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_cancelled_writer_hands_off() {
+    cancelled_writer_hands_off(false);
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_cancelled_queued_writer_hands_off() {
+    cancelled_writer_hands_off(true);
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_cancelled_initiator_and_reader() {
+    common::model(|| {
+        let target = Arc::new(Atomic::new(0, parked_control(1, false)));
+        let token = CancelToken::new();
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 1))
+        };
+        let cancellable = {
+            let target = target.clone();
+            let token = token.clone();
+            thread::spawn(move || target.write_cancellable(&token, |val| *val + 1))
+        };
+        token.cancel();
+
+        let written = cancellable.join().expect("").map_or(0, |_| 1);
+        reader.join().expect("");
+        target.write(|val| *val + 1);
+        assert_eq!(1 + written, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_cancel_writer_waiting_for_readers() {
+    let target = Arc::new(Atomic::new(0, CASAccessControl::new(1)));
+    let token = CancelToken::new();
+    let reader = target.control().read();
+
+    let writer = {
+        let target = target.clone();
+        let token = token.clone();
+        thread::spawn(move || target.write_cancellable(&token, |val| val + 1))
+    };
+    // Opened his phase and left the pending writers, only the reader holds him back.
+    while target.control().write_phases() == 0 || target.control().pending_writers() > 0 {
+        thread::yield_now();
+    }
+    token.cancel();
+    assert_eq!(Err(WriteError::Cancelled), writer.join().expect(""));
+
+    drop(reader);
+    assert_eq!(0, *target.read());
+    target.write(|val| val + 1);
+    assert_eq!(1, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_cancelled_before_waiting() {
+    let target = Atomic::new(0, CASAccessControl::new(1));
+    let token = CancelToken::new();
+    token.cancel();
+
+    assert_eq!(
+        Err(WriteError::Cancelled),
+        target.write_cancellable(&token, |val| val + 1)
+    );
+    target.write(|val| val + 1);
+    assert_eq!(1, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_cancel_lock_writer() {
+    let target = Arc::new(Atomic::new(0, LockAccessControl::default()));
+    let token = CancelToken::new();
+    let reader = target.control().read();
+
+    let writer = {
+        let target = target.clone();
+        let token = token.clone();
+        thread::spawn(move || target.write_cancellable(&token, |val| val + 1))
+    };
+    while target.control().polling_writers() == 0 {
+        thread::yield_now();
+    }
+    token.cancel();
+    assert_eq!(Err(WriteError::Cancelled), writer.join().expect(""));
+    assert_eq!(0, target.control().polling_writers());
+
+    drop(reader);
+    assert_eq!(
+        Ok(()),
+        target.write_cancellable(&CancelToken::new(), |val| val + 1)
+    );
+    assert_eq!(1, *target.read());
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_random_cancellations(max_write_line in 1u16..6, mode in 0u8..3, readers in 0usize..3, writers in 1usize..4, cancellable in 1usize..4, writes in 1usize..24, cancel_after in 0u64..300) {
+        let control = CASAccessControl::new(max_write_line);
+        let control = match mode {
+            0 => control,
+            1 => control.with_queued_writers(),
+            _ => control.with_fifo_writers(),
+        };
+        let target = Arc::new(Atomic::new(0usize, control));
+        let token = CancelToken::new();
+
+        // The first writers never give up, the others do once cancelled.
+        let write_token = token.clone();
+        let written = common::run_load(
+            &target,
+            readers,
+            writers + cancellable,
+            writes,
+            move |target, writer, _| {
+                if writer < writers {
+                    target.write(|val| *val + 1);
+                    true
+                } else {
+                    target.write_cancellable(&write_token, |val| *val + 1).is_ok()
+                }
+            },
+            || {
+                thread::sleep(Duration::from_micros(cancel_after));
+                token.cancel();
+            },
+        );
+        assert!(writers * writes <= written);
+        assert_eq!(0, target.control().pending_writers());
+        target.write(|val| *val + 1);
+        assert_eq!(written + 1, *target.read());
+    }
+}
@@ -0,0 +1,272 @@
+mod common;
+
+use lib::access::cas::CASAccessControl;
+use lib::atomic::{Atomic, WriteError};
+
+#[cfg(not(loom))]
+use lib::access::AtomicAccessControl;
+#[cfg(not(loom))]
+use lib::access::backoff::Park;
+#[cfg(not(loom))]
+use lib::access::cancel::CancelToken;
+#[cfg(not(loom))]
+use lib::access::wait_free::WaitFreeAccessControl;
+#[cfg(not(loom))]
+use lib::atomic::FrozenWrites;
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::{Arc, Barrier};
+#[cfg(not(loom))]
+use std::thread;
+#[cfg(not(loom))]
+use std::time::Duration;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(loom)]
+#[test]
+fn test_loom_close_with_reader_and_writer() {
+    common::model(|| {
+        let control = common::parked(CASAccessControl::new(1));
+        let target = Arc::new(Atomic::new(0, control));
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 1))
+        };
+        let writer = {
+            let target = target.clone();
+            thread::spawn(move || target.try_write(|val| *val + 1))
+        };
+        target.close();
+
+        let written = writer.join().expect("");
+        reader.join().expect("");
+        let expected = if written == Err(WriteError::Closed) {
+            0
+        } else {
+            1
+        };
+        assert_eq!(expected, *target.read());
+        assert_eq!(Err(WriteError::Closed), target.try_write(|val| *val + 1));
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_close_releases_waiting_writers() {
+    common::model(|| {
+        let control = common::parked(CASAccessControl::new(2));
+        let target = Arc::new(Atomic::new(0, control));
+
+        let writers: Vec<_> = (0..2)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || target.try_write(|val| *val + 1).map_or(0, |_| 1))
+            })
+            .collect();
+        target.close();
+
+        let written: i32 = writers
+            .into_iter()
+            .map(|writer| writer.join().expect(""))
+            .sum();
+        assert_eq!(written, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_closed_reads_and_writes() {
+    let target = Atomic::new_cas(0, 1);
+    target.write(|val| val + 1);
+    assert!(!target.is_closed());
+
+    target.close();
+    target.close();
+    assert!(target.is_closed());
+    assert!(target.is_frozen());
+    assert_eq!(1, *target.read());
+    assert_eq!(Err(WriteError::Closed), target.try_write(|val| val + 1));
+    assert_eq!(
+        Err(WriteError::Closed),
+        target.write_cancellable(&CancelToken::new(), |val| val + 1)
+    );
+
+    target.freeze();
+    assert_eq!(1, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+#[should_panic(expected = "write to a closed atomic")]
+fn test_closed_write_panics() {
+    let target = Atomic::new_lock(0);
+    target.close();
+    target.write(|val| val + 1);
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_close_frozen_atomic() {
+    let target = Atomic::new_cas(0, 1);
+    target.freeze();
+    target.close();
+    assert_eq!(Err(WriteError::Closed), target.try_write(|val| val + 1));
+    assert_eq!(0, *target.read());
+}
+
+// A plain write blocked behind a reader when the atomic closes. Returns how the writer thread ended.
+#[cfg(not(loom))]
+fn close_blocked_write(frozen_writes: FrozenWrites) -> thread::Result<()> {
+    let target = Arc::new(Atomic::new_cas(0, 1).with_frozen_writes(frozen_writes));
+    let reader = target.control().read();
+
+    let writer = {
+        let target = target.clone();
+        thread::spawn(move || target.write(|val| val + 1))
+    };
+    // Opened his phase, waits for the reader to leave.
+    while target.control().write_phases() == 0 {
+        thread::yield_now();
+    }
+    let closer = {
+        let target = target.clone();
+        thread::spawn(move || target.close())
+    };
+    let written = writer.join();
+    drop(reader);
+    closer.join().expect("");
+    assert_eq!(0, *target.read());
+    written
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_close_panics_blocked_write() {
+    let panic = close_blocked_write(FrozenWrites::Panic).expect_err("");
+    assert_eq!(
+        Some(&"write to a closed atomic".to_string()),
+        panic.downcast_ref::<String>()
+    );
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_close_drops_ignored_blocked_write() {
+    close_blocked_write(FrozenWrites::Ignore).expect("");
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_close_releases_parked_writers() {
+    let control = CASAccessControl::new(1).with_backoff(Park::default());
+    let target = Arc::new(Atomic::new(0, control));
+    let reader = target.control().read();
+
+    let writers: Vec<_> = (0..3)
+        .map(|_| {
+            let target = target.clone();
+            thread::spawn(move || target.try_write(|val| val + 1))
+        })
+        .collect();
+    while target.control().pending_writers() < 2 {
+        thread::yield_now();
+    }
+    thread::sleep(Duration::from_millis(10));
+
+    let closer = {
+        let target = target.clone();
+        thread::spawn(move || target.close())
+    };
+    for writer in writers {
+        assert_eq!(Err(WriteError::Closed), writer.join().expect(""));
+    }
+    drop(reader);
+    closer.join().expect("");
+    assert_eq!(0, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_close_lock_waits_for_writer() {
+    let target = Arc::new(Atomic::new_lock(0));
+    let writer = target.control().write();
+
+    let closer = {
+        let target = target.clone();
+        thread::spawn(move || target.close())
+    };
+    thread::sleep(Duration::from_millis(10));
+    assert!(!target.is_frozen());
+
+    drop(writer);
+    closer.join().expect("");
+    assert_eq!(Err(WriteError::Closed), target.try_write(|val| val + 1));
+    assert_eq!(0, *target.read());
+}
+
+// Readers of the wait-free control don't hold writers back, the close must still wait for the one already writing.
+#[cfg(not(loom))]
+#[test]
+fn test_close_wait_free_waits_for_writer() {
+    let target = Arc::new(Atomic::new(0, WaitFreeAccessControl::new(1)));
+    let writing = Arc::new(Barrier::new(2));
+
+    let writer = {
+        let target = target.clone();
+        let writing = writing.clone();
+        thread::spawn(move || {
+            target.try_write(|val| {
+                writing.wait();
+                writing.wait();
+                val + 1
+            })
+        })
+    };
+    writing.wait();
+    let closer = {
+        let target = target.clone();
+        thread::spawn(move || target.close())
+    };
+    thread::sleep(Duration::from_millis(10));
+    assert!(!target.is_frozen());
+
+    writing.wait();
+    assert_eq!(Ok(()), writer.join().expect(""));
+    closer.join().expect("");
+    assert!(target.is_frozen());
+    assert_eq!(1, *target.read());
+    assert_eq!(Err(WriteError::Closed), target.try_write(|val| val + 1));
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_close_under_load(max_write_line in 1u16..6, readers in 0usize..4, writers in 1usize..5, writes in 1usize..48, close_after in 0u64..300) {
+        let control = CASAccessControl::new(max_write_line);
+        let target = Arc::new(Atomic::new(0usize, control));
+
+        let mut closed = 0;
+        let written = common::run_load(
+            &target,
+            readers,
+            writers,
+            writes,
+            |target, _, _| target.try_write(|val| *val + 1).is_ok(),
+            || {
+                thread::sleep(Duration::from_micros(close_after));
+                target.close();
+                closed = *target.read();
+            },
+        );
+        assert_eq!(written, closed);
+        assert_eq!(0, target.control().pending_writers());
+        assert_eq!(Err(WriteError::Closed), target.try_write(|val| *val + 1));
+    }
+}
@@ -0,0 +1,128 @@
+// Shared by the integration tests, each test crate uses only part of it.
+#![allow(dead_code)]
+
+#[cfg(loom)]
+use lib::access::backoff::Park;
+#[cfg(loom)]
+use lib::access::cas::CASAccessControl;
+
+#[cfg(not(loom))]
+use lib::access::AtomicAccessControl;
+#[cfg(not(loom))]
+use lib::atomic::Atomic;
+#[cfg(not(loom))]
+use std::fmt::Debug;
+#[cfg(not(loom))]
+use std::sync::Arc;
+#[cfg(not(loom))]
+use std::thread::{self, JoinHandle};
+
+// Three threads explode the interleavings past a preemption bound of two.
+#[cfg(loom)]
+pub fn model<F: Fn() + Sync + Send + 'static>(f: F) {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound = Some(2);
+    builder.check(f);
+}
+
+// Spinning waiters blow up the explored interleavings, models park instead.
+#[cfg(loom)]
+pub fn parked(control: CASAccessControl) -> CASAccessControl<Park> {
+    control.with_backoff(Park::new(0))
+}
+
+// Readers checking the value, as measured by `key`, never goes back.
+#[cfg(not(loom))]
+pub fn spawn_readers<T, A>(
+    target: &Arc<Atomic<T, A>>,
+    readers: usize,
+    reads: usize,
+    key: fn(&T) -> usize,
+) -> Vec<JoinHandle<()>>
+where
+    T: Debug + Send + Sync + 'static,
+    A: AtomicAccessControl + 'static,
+{
+    (0..readers)
+        .map(|_| {
+            let target = target.clone();
+            thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..reads {
+                    let current = key(&target.read());
+                    assert!(last <= current);
+                    last = current;
+                    thread::yield_now();
+                }
+            })
+        })
+        .collect()
+}
+
+// Writers each call `write_fn` `writes` times, given their index and the one of the write, while readers check the value
+// never goes back and `during_fn` runs on this thread. `write_fn` tells whether its write landed, the final value must be
+// the count of those. Returns it.
+#[cfg(not(loom))]
+pub fn run_load<A, W, D>(
+    target: &Arc<Atomic<usize, A>>,
+    readers: usize,
+    writers: usize,
+    writes: usize,
+    write_fn: W,
+    during_fn: D,
+) -> usize
+where
+    A: AtomicAccessControl + 'static,
+    W: Fn(&Atomic<usize, A>, usize, usize) -> bool + Send + Sync + 'static,
+    D: FnOnce(),
+{
+    let write_fn = Arc::new(write_fn);
+    let reader_workers = spawn_readers(target, readers, writes, |val| *val);
+    let writer_workers: Vec<_> = (0..writers)
+        .map(|writer| {
+            let (target, write_fn) = (target.clone(), write_fn.clone());
+            thread::spawn(move || {
+                (0..writes)
+                    .filter(|write| write_fn(&target, writer, *write))
+                    .count()
+            })
+        })
+        .collect();
+    during_fn();
+
+    let written = writer_workers
+        .into_iter()
+        .map(|worker| worker.join().expect(""))
+        .sum();
+    reader_workers
+        .into_iter()
+        .for_each(|worker| worker.join().expect(""));
+    assert_eq!(written, *target.read());
+    written
+}
+
+// Plain increments, every one of them lands.
+#[cfg(not(loom))]
+pub fn run_increments<A, D>(
+    target: &Arc<Atomic<usize, A>>,
+    readers: usize,
+    writers: usize,
+    writes: usize,
+    during_fn: D,
+) where
+    A: AtomicAccessControl + 'static,
+    D: FnOnce(),
+{
+    let written = run_load(
+        target,
+        readers,
+        writers,
+        writes,
+        |target, _, _| {
+            target.write(|val| *val + 1);
+            true
+        },
+        during_fn,
+    );
+    assert_eq!(writers * writes, written);
+}
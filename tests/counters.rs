@@ -0,0 +1,42 @@
+#![cfg(not(loom))]
+
+use lib::access::AtomicAccessControl;
+use lib::access::cas::CASAccessControl;
+use lib::atomic::Atomic;
+
+type Control = CASAccessControl;
+
+#[test]
+#[cfg(not(feature = "wide-counters"))]
+fn test_readers_up_to_the_limit() {
+    let control = Control::new(1);
+    let guards: Vec<_> = (0..Control::MAX_READERS).map(|_| control.read()).collect();
+    drop(guards);
+
+    let target = Atomic::new(0, control);
+    target.write(|val| val + 1);
+    assert_eq!(1, *target.read());
+}
+
+#[test]
+#[cfg(not(feature = "wide-counters"))]
+#[should_panic(expected = "Too many readers")]
+fn test_readers_overflow_is_detected() {
+    let control = Control::new(1);
+    let _guards: Vec<_> = (0..=Control::MAX_READERS).map(|_| control.read()).collect();
+}
+
+#[test]
+#[cfg(feature = "wide-counters")]
+fn test_wide_counters_hold_more_readers() {
+    let control = Control::new(1);
+    assert!(Control::MAX_READERS > u64::from(u16::MAX));
+    assert!(Control::MAX_PENDING_WRITERS > u64::from(u16::MAX));
+
+    let guards: Vec<_> = (0..=u64::from(u16::MAX)).map(|_| control.read()).collect();
+    drop(guards);
+
+    let target = Atomic::new(0, control);
+    target.write(|val| val + 1);
+    assert_eq!(1, *target.read());
+}
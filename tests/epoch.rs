@@ -0,0 +1,88 @@
+mod common;
+
+use lib::access::epoch::EpochAccessControl;
+use lib::atomic::Atomic;
+
+#[cfg(not(loom))]
+use lib::access::AtomicAccessControl;
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::Arc;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(loom)]
+#[test]
+fn test_loom_reader_never_sees_released_version() {
+    loom::model(|| {
+        let target = Arc::new(Atomic::new(0, EpochAccessControl::new(1)));
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 3))
+        };
+        target.write(|val| *val + 1);
+        target.write(|val| *val + 1);
+        target.write(|val| *val + 1);
+
+        reader.join().expect("");
+        assert_eq!(3, *target.read());
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_writers_serialize() {
+    loom::model(|| {
+        let target = Arc::new(Atomic::new(0, EpochAccessControl::new(2)));
+
+        let writer = {
+            let target = target.clone();
+            thread::spawn(move || target.write(|val| *val + 1))
+        };
+        let value = *target.read();
+        target.write(|val| *val + 1);
+        assert!(value <= 1);
+
+        writer.join().expect("");
+        assert_eq!(2, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_pinned_reader_holds_back_release() {
+    let target = Atomic::new(0, EpochAccessControl::new(1));
+
+    let pin = target.control().read();
+    (0..3).for_each(|_| target.write(|val| *val + 1));
+    assert_eq!(3, target.control().pending_garbage());
+    assert_eq!(3, *target.read());
+
+    drop(pin);
+    (0..2).for_each(|_| target.write(|val| *val + 1));
+    assert_eq!(1, target.control().pending_garbage());
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_epoch_reads_and_writes(max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..4, writes in 1usize..64) {
+        let target = Arc::new(Atomic::new(0usize, EpochAccessControl::new(max_write_line)));
+
+        common::run_increments(&target, readers, writers, writes, || {
+            // Pinned reads don't wait for the held write access.
+            let writing = target.control().write();
+            assert_eq!(*target.read(), *target.read());
+            drop(writing);
+        });
+        // Unpinned, the epochs advance past whatever the load left behind.
+        (0..2).for_each(|_| target.write(|val| *val + 1));
+        assert_eq!(1, target.control().pending_garbage());
+    }
+}
@@ -0,0 +1,117 @@
+use lib::atomic::Atomic;
+use lib::group::AtomicGroup;
+use lib::snapshot;
+
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::Arc;
+#[cfg(not(loom))]
+use std::thread;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(loom)]
+#[test]
+fn test_loom_exchange_is_never_observed_twice() {
+    loom::model(|| {
+        let group = AtomicGroup::new();
+        let front = Arc::new(Atomic::new_cas(1, 1).in_group(&group));
+        let back = Arc::new(Atomic::new_lock(2).in_group(&group));
+
+        let swapper = {
+            let (front, back) = (front.clone(), back.clone());
+            thread::spawn(move || front.exchange_with(&*back))
+        };
+
+        let (front_val, back_val) = snapshot!(*front, *back);
+        assert_ne!(*front_val, *back_val);
+
+        swapper.join().expect("");
+        assert_eq!(2, *front.read());
+        assert_eq!(1, *back.read());
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_crossed_exchanges() {
+    loom::model(|| {
+        let front = Arc::new(Atomic::new_cas(1, 1));
+        let back = Arc::new(Atomic::new_cas(2, 1));
+
+        let swapper = {
+            let (front, back) = (front.clone(), back.clone());
+            thread::spawn(move || back.exchange_with(&*front))
+        };
+        front.exchange_with(&*back);
+
+        swapper.join().expect("");
+        assert_eq!(1, *front.read());
+        assert_eq!(2, *back.read());
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_exchange_with_write() {
+    loom::model(|| {
+        let front = Arc::new(Atomic::new_cas(1, 1));
+        let back = Arc::new(Atomic::new_cas(10, 1));
+
+        let writer = {
+            let front = front.clone();
+            thread::spawn(move || front.write(|val| *val + 1))
+        };
+        front.exchange_with(&*back);
+
+        writer.join().expect("");
+        // Either the write lands before the exchange and travels to back, or after it over the old back value.
+        let values = (*front.read(), *back.read());
+        assert!(values == (10, 2) || values == (11, 1));
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_exchange_with_itself_is_noop() {
+    let front = Atomic::new_cas(1, 1);
+    front.exchange_with(&front);
+    assert_eq!(1, *front.read());
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_double_buffer_exchange(num_swappers in 2usize..6, num_worker_swaps in 100usize..1000) {
+        let group = AtomicGroup::new();
+        let front = Arc::new(Atomic::new_cas(1, 2).in_group(&group));
+        let back = Arc::new(Atomic::new_lock(2).in_group(&group));
+
+        let workers: Vec<_> = (0..num_swappers)
+            .map(|worker| {
+                let (front, back) = (front.clone(), back.clone());
+                thread::spawn(move || {
+                    (0..num_worker_swaps).for_each(|_| {
+                        if worker % 2 == 0 {
+                            front.exchange_with(&*back);
+                        } else {
+                            back.exchange_with(&*front);
+                        }
+                        let (front_val, back_val) = snapshot!(*front, *back);
+                        assert_eq!(3, *front_val + *back_val);
+                    })
+                })
+            })
+            .collect();
+        workers.into_iter().for_each(|worker| worker.join().expect(""));
+
+        let expected_front = if (num_swappers * num_worker_swaps) % 2 == 0 { 1 } else { 2 };
+        assert_eq!(expected_front, *front.read());
+        assert_eq!(3 - expected_front, *back.read());
+    }
+}
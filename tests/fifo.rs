@@ -0,0 +1,114 @@
+#![cfg(not(loom))]
+
+mod common;
+
+use lib::access::cas::CASAccessControl;
+use lib::atomic::Atomic;
+use proptest::proptest;
+use std::sync::{Arc, mpsc};
+use std::thread;
+
+fn fifo_atomic(max_write_line: u16, queued: bool) -> Arc<Atomic<Vec<usize>, CASAccessControl>> {
+    let control = CASAccessControl::new(max_write_line).with_fifo_writers();
+    let control = if queued {
+        control.with_queued_writers()
+    } else {
+        control
+    };
+    Arc::new(Atomic::new(vec![], control))
+}
+
+// Writers arrive one by one behind a blocked write, each once the previous one holds his ticket.
+fn commit_order(
+    target: &Arc<Atomic<Vec<usize>, CASAccessControl>>,
+    writers: usize,
+    readers: usize,
+) -> Vec<usize> {
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let blocker = {
+        let target = target.clone();
+        thread::spawn(move || {
+            target.write(|val| {
+                started_tx.send(()).expect("");
+                release_rx.recv().expect("");
+                val.clone()
+            })
+        })
+    };
+    started_rx.recv().expect("");
+
+    let reader_workers = common::spawn_readers(target, readers, writers, Vec::len);
+    let issued = target.control().tickets_issued();
+    let writer_workers: Vec<_> = (0..writers)
+        .map(|id| {
+            let writer = {
+                let target = target.clone();
+                thread::spawn(move || {
+                    target.write(|val| {
+                        let mut val = val.clone();
+                        val.push(id);
+                        val
+                    })
+                })
+            };
+            while target.control().tickets_issued() < issued + id as u64 + 1 {
+                thread::yield_now();
+            }
+            writer
+        })
+        .collect();
+
+    release_tx.send(()).expect("");
+    blocker.join().expect("");
+    writer_workers
+        .into_iter()
+        .chain(reader_workers)
+        .for_each(|worker| worker.join().expect(""));
+    target.read().to_vec()
+}
+
+#[test]
+fn test_writes_commit_in_arrival_order() {
+    let target = fifo_atomic(4, false);
+    assert_eq!((0..8).collect::<Vec<_>>(), commit_order(&target, 8, 0));
+}
+
+proptest! {
+
+    #[test]
+    fn test_fifo_commit_order(max_write_line in 1u16..8, queued in proptest::bool::ANY, readers in 0usize..3, writers in 1usize..12) {
+        let target = fifo_atomic(max_write_line, queued);
+        assert_eq!((0..writers).collect::<Vec<_>>(), commit_order(&target, writers, readers));
+    }
+
+    #[test]
+    fn test_fifo_writes_per_thread_in_order(max_write_line in 1u16..8, queued in proptest::bool::ANY, writers in 1usize..4, writes in 1usize..32) {
+        let target = fifo_atomic(max_write_line, queued);
+
+        let writer_workers: Vec<_> = (0..writers)
+            .map(|id| {
+                let target = target.clone();
+                thread::spawn(move || {
+                    (0..writes).for_each(|write| {
+                        target.write(|val| {
+                            let mut val = val.clone();
+                            val.push(id * writes + write);
+                            val
+                        })
+                    })
+                })
+            })
+            .collect();
+        writer_workers
+            .into_iter()
+            .for_each(|worker| worker.join().expect(""));
+
+        let committed = target.read();
+        assert_eq!(writers * writes, committed.len());
+        for id in 0..writers {
+            let own: Vec<_> = committed.iter().filter(|write| **write / writes == id).collect();
+            assert!(own.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+}
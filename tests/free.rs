@@ -1,3 +1,5 @@
+use lib::access::epoch::EpochAccessControl;
+use lib::access::hazard::HazardAccessControl;
 use lib::atomic::Atomic;
 use std::alloc::{GlobalAlloc, System};
 use std::fmt::Debug;
@@ -27,7 +29,7 @@ proptest! {
     #[cfg(not(loom))]
     #[test]
     fn test_atomic_cas_memory_free(num_readers in 4usize..6, num_writers in 4usize..6, num_worker_writes in 1000usize..10000) {
-        perform(num_readers, num_writers, num_worker_writes, Atomic::new_cas(0, u16::MAX));
+        perform(num_readers, num_writers, num_worker_writes, || Atomic::new_cas(0, u16::MAX));
 
 }
 
@@ -35,7 +37,23 @@ proptest! {
     #[cfg(not(loom))]
     #[test]
     fn test_atomic_lock_memory_free(num_readers in 4usize..6, num_writers in 4usize..6, num_worker_writes in 1000usize..10000) {
-        perform(num_readers, num_writers, num_worker_writes, Atomic::new_lock(0));
+        perform(num_readers, num_writers, num_worker_writes, || Atomic::new_lock(0));
+
+}
+
+
+    #[cfg(not(loom))]
+    #[test]
+    fn test_atomic_epoch_memory_free(num_readers in 4usize..6, num_writers in 4usize..6, num_worker_writes in 1000usize..10000) {
+        perform(num_readers, num_writers, num_worker_writes, || Atomic::new(0, EpochAccessControl::new(u16::MAX)));
+
+}
+
+
+    #[cfg(not(loom))]
+    #[test]
+    fn test_atomic_hazard_memory_free(num_readers in 4usize..6, num_writers in 4usize..6, num_worker_writes in 1000usize..10000) {
+        perform(num_readers, num_writers, num_worker_writes, || Atomic::new(0, HazardAccessControl::new(u16::MAX)));
 
 }
 
@@ -45,9 +63,11 @@ fn perform<T: lib::tests::ReadWriteExt<usize> + 'static>(
     num_readers: usize,
     num_writers: usize,
     num_worker_writes: usize,
-    target: T,
+    target_fn: impl FnOnce() -> T,
 ) {
     GLOBAL_ALLOCATOR.reset();
+    // Built after the reset, so whatever the target allocates up front is counted as well.
+    let target = target_fn();
 
     let handle = lib::tests::runtime(num_readers, num_writers, Arc::new(target));
 
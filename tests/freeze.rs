@@ -0,0 +1,179 @@
+mod common;
+
+use lib::access::cas::CASAccessControl;
+use lib::atomic::{Atomic, WriteError};
+
+#[cfg(not(loom))]
+use lib::access::AtomicAccessControl;
+#[cfg(not(loom))]
+use lib::atomic::FrozenWrites;
+#[cfg(not(loom))]
+use lib::transaction::transaction;
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::Arc;
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(loom))]
+use std::thread;
+#[cfg(not(loom))]
+use std::time::Duration;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(loom)]
+#[test]
+fn test_loom_freeze_with_reader_and_writer() {
+    common::model(|| {
+        let control = common::parked(CASAccessControl::new(1));
+        let target = Arc::new(Atomic::new(0, control));
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 1))
+        };
+        let writer = {
+            let target = target.clone();
+            thread::spawn(move || target.try_write(|val| *val + 1))
+        };
+        target.freeze();
+
+        let written = writer.join().expect("");
+        reader.join().expect("");
+        let expected = if written == Err(WriteError::Frozen) {
+            0
+        } else {
+            1
+        };
+        assert_eq!(expected, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_frozen_reads_and_writes() {
+    let target = Atomic::new_cas(0, 1);
+    target.write(|val| val + 1);
+    assert!(!target.is_frozen());
+
+    target.freeze();
+    target.freeze();
+    assert!(target.is_frozen());
+    assert_eq!(1, *target.read());
+    assert_eq!(Err(WriteError::Frozen), target.try_write(|val| val + 1));
+    assert_eq!((Arc::new(1), 2), target.read_versioned());
+}
+
+#[cfg(not(loom))]
+#[test]
+#[should_panic(expected = "write to a frozen atomic")]
+fn test_frozen_write_panics() {
+    let target = Atomic::new_cas(0, 1);
+    target.freeze();
+    target.write(|val| val + 1);
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_ignored_frozen_writes() {
+    let target = Atomic::new_lock(0).with_frozen_writes(FrozenWrites::Ignore);
+    target.freeze();
+
+    target.write(|val| val + 1);
+    assert_eq!(0, *target.read_in_write(|val| val + 1));
+    assert_eq!(Err(WriteError::Frozen), target.try_write(|val| val + 1));
+    assert_eq!(0, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+#[should_panic(expected = "write to a frozen atomic")]
+fn test_frozen_transaction_panics() {
+    let first = Atomic::new_cas(0, 1);
+    let second = Atomic::new_cas(0, 1);
+    second.freeze();
+
+    transaction(|tx| {
+        tx.write(&first, |val| val + 1);
+        tx.write(&second, |val| val + 1);
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_failed_frozen_writes_release_the_access() {
+    let first = Arc::new(Atomic::new_cas(0, 1));
+    let second = Atomic::new_lock(0);
+    second.freeze();
+
+    let exchange = {
+        let first = first.clone();
+        thread::spawn(move || first.exchange_with(&second))
+    };
+    assert!(exchange.join().is_err());
+
+    first.write(|val| val + 1);
+    assert_eq!(1, *first.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_freeze_waits_for_active_reader() {
+    let target = Arc::new(Atomic::new_cas(0, 1));
+    let frozen = Arc::new(AtomicBool::new(false));
+    let reader = target.control().read();
+
+    let freezer = {
+        let target = target.clone();
+        let frozen = frozen.clone();
+        thread::spawn(move || {
+            target.freeze();
+            frozen.store(true, Ordering::Release);
+        })
+    };
+    thread::sleep(Duration::from_millis(10));
+    assert!(!frozen.load(Ordering::Acquire));
+
+    drop(reader);
+    freezer.join().expect("");
+    assert!(target.is_frozen());
+    assert_eq!(0, *target.read());
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_freeze_under_load(max_write_line in 1u16..6, readers in 0usize..4, writers in 1usize..5, writes in 1usize..48, freeze_after in 0u64..300) {
+        let control = CASAccessControl::new(max_write_line);
+        let target = Arc::new(Atomic::new(0usize, control));
+
+        let mut frozen = 0;
+        let written = common::run_load(
+            &target,
+            readers,
+            writers,
+            writes,
+            |target, _, _| match target.try_write(|val| *val + 1) {
+                Ok(()) => true,
+                Err(error) => {
+                    // Turned away, nothing moves anymore.
+                    assert_eq!(WriteError::Frozen, error);
+                    assert_eq!(*target.read(), *target.read());
+                    false
+                }
+            },
+            || {
+                thread::sleep(Duration::from_micros(freeze_after));
+                target.freeze();
+                frozen = *target.read();
+            },
+        );
+        assert_eq!(written, frozen);
+        assert_eq!(0, target.control().pending_writers());
+    }
+}
@@ -0,0 +1,61 @@
+#![cfg(not(loom))]
+
+use lib::atomic::Atomic;
+use lib::group::AtomicGroup;
+use lib::snapshot;
+use proptest::proptest;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+#[test]
+#[should_panic(expected = "must belong to a group")]
+fn test_snapshot_requires_group() {
+    let a = Atomic::new_cas(0, 1);
+    let b = Atomic::new_cas(0, 1);
+    let _ = snapshot!(a, b);
+}
+
+#[test]
+#[should_panic(expected = "must belong to the group")]
+fn test_read_all_rejects_foreign_members() {
+    let group = AtomicGroup::new();
+    let a = Atomic::new_cas(0, 1).in_group(&group);
+    let b = Atomic::new_lock(0).in_group(&AtomicGroup::new());
+    let _ = group.read_all((&a, &b));
+}
+
+proptest! {
+
+    #[test]
+    fn test_snapshot_is_consistent(num_readers in 2usize..4, num_writes in 1000usize..5000) {
+        let group = AtomicGroup::new();
+        let routes = Arc::new(Atomic::new_cas(0usize, 1).in_group(&group));
+        let acl = Arc::new(Atomic::new_lock(0usize).in_group(&group));
+        let done = Arc::new(AtomicBool::new(false));
+
+        let readers: Vec<_> = (0..num_readers)
+            .map(|_| {
+                let (routes, acl, done) = (routes.clone(), acl.clone(), done.clone());
+                thread::spawn(move || {
+                    while !done.load(Ordering::Acquire) {
+                        // Routes are always written first, so a consistent cut is at most one write ahead.
+                        let (routes, acl) = snapshot!(*routes, *acl);
+                        assert!(*routes == *acl || *routes == *acl + 1);
+                    }
+                })
+            })
+            .collect();
+
+        (0..num_writes).for_each(|_| {
+            routes.write(|val| *val + 1);
+            acl.write(|val| *val + 1);
+        });
+        done.store(true, Ordering::Release);
+        readers.into_iter().for_each(|reader| reader.join().expect(""));
+
+        let (routes, acl) = group.read_all((&*routes, &*acl));
+        assert_eq!(num_writes, *routes);
+        assert_eq!(num_writes, *acl);
+    }
+}
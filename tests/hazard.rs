@@ -0,0 +1,118 @@
+mod common;
+
+#[cfg(not(loom))]
+use lib::access::cas::CASAccessControl;
+use lib::access::hazard::HazardAccessControl;
+use lib::atomic::Atomic;
+
+#[cfg(not(loom))]
+use lib::access::AtomicAccessControl;
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::{Arc, mpsc};
+#[cfg(not(loom))]
+use std::thread;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(loom)]
+#[test]
+fn test_loom_reader_never_sees_released_version() {
+    loom::model(|| {
+        let target = Arc::new(Atomic::new(0, HazardAccessControl::new(1)));
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 2))
+        };
+        target.write(|val| *val + 1);
+        target.write(|val| *val + 1);
+
+        reader.join().expect("");
+        assert_eq!(2, *target.read());
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_writers_serialize() {
+    loom::model(|| {
+        let target = Arc::new(Atomic::new(0, HazardAccessControl::new(2)));
+
+        let writer = {
+            let target = target.clone();
+            thread::spawn(move || target.write(|val| *val + 1))
+        };
+        let value = *target.read();
+        target.write(|val| *val + 1);
+        assert!(value <= 1);
+
+        writer.join().expect("");
+        assert_eq!(2, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_read_while_write_closure_runs() {
+    let target = Arc::new(Atomic::new(0, HazardAccessControl::new(1)));
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+
+    let writer = {
+        let target = target.clone();
+        thread::spawn(move || {
+            target.write(|val| {
+                started_tx.send(()).expect("");
+                release_rx.recv().expect("");
+                val + 1
+            })
+        })
+    };
+
+    started_rx.recv().expect("");
+    assert_eq!(0, *target.read());
+    assert_eq!((Arc::new(0), 0), target.read_versioned());
+    release_tx.send(()).expect("");
+
+    writer.join().expect("");
+    assert_eq!(1, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_unreleased_versions_bounded() {
+    let target = Atomic::new(
+        0,
+        HazardAccessControl::with_slots(CASAccessControl::new(1), 2),
+    );
+
+    for _ in 0..16 {
+        target.write(|val| *val + 1);
+        assert!(target.control().pending_garbage() <= target.control().slots() + 1);
+    }
+    assert_eq!(16, *target.read());
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_hazard_reads_and_writes(max_write_line in 1u16..4, slots in 1usize..4, readers in 1usize..4, writers in 1usize..4, writes in 1usize..64) {
+        let control = HazardAccessControl::with_slots(CASAccessControl::new(max_write_line), slots);
+        let target = Arc::new(Atomic::new(0usize, control));
+
+        common::run_increments(&target, readers, writers, writes, || {
+            // Protected reads don't wait for the held write access.
+            let writing = target.control().write();
+            assert_eq!(*target.read(), *target.read());
+            drop(writing);
+        });
+        // No slot publishes anything anymore.
+        assert!(target.control().pending_garbage() <= target.control().slots());
+    }
+}
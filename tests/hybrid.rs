@@ -0,0 +1,121 @@
+mod common;
+
+use lib::access::cas::CASAccessControl;
+use lib::access::hybrid::HybridAccessControl;
+use lib::access::policy::HybridSwitch;
+use lib::atomic::Atomic;
+
+#[cfg(not(loom))]
+use lib::access::AtomicAccessControl;
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::Arc;
+#[cfg(not(loom))]
+use std::thread;
+#[cfg(not(loom))]
+use std::time::Duration;
+
+#[cfg(loom)]
+use lib::access::backoff::Park;
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+// Ends a window on every write: any long wait moves to the lock, a write without contention moves back.
+fn eager_switch() -> HybridSwitch {
+    HybridSwitch::new(1, 1, 1, 0)
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_switching_writers_and_reader() {
+    common::model(|| {
+        let control = HybridAccessControl::with_switch(
+            CASAccessControl::new(1).with_backoff(Park::new(1)),
+            eager_switch(),
+        );
+        let target = Arc::new(Atomic::new(0, control));
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 2))
+        };
+        let writer = {
+            let target = target.clone();
+            thread::spawn(move || target.write(|val| *val + 1))
+        };
+        target.write(|val| *val + 1);
+
+        writer.join().expect("");
+        reader.join().expect("");
+        assert_eq!(2, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_switches_to_lock_and_back() {
+    let target = Arc::new(Atomic::new(
+        0,
+        HybridAccessControl::with_switch(CASAccessControl::new(1), eager_switch()),
+    ));
+    assert!(!target.control().is_locking());
+
+    // The writer spins on the CAS path until the reader leaves.
+    let reader = target.control().read();
+    let writer = {
+        let target = target.clone();
+        thread::spawn(move || target.write(|val| val + 1))
+    };
+    thread::sleep(Duration::from_millis(10));
+    drop(reader);
+    writer.join().expect("");
+    assert!(target.control().is_locking());
+    assert_eq!(1, target.control().switches());
+
+    // Blocked on the lock by a reader, the writer keeps the lock path.
+    let reader = target.control().read();
+    let writer = {
+        let target = target.clone();
+        thread::spawn(move || target.write(|val| val + 1))
+    };
+    thread::sleep(Duration::from_millis(10));
+    drop(reader);
+    writer.join().expect("");
+    assert!(target.control().is_locking());
+
+    // Uncontended, the next one goes back to CAS.
+    target.write(|val| val + 1);
+    assert!(!target.control().is_locking());
+    assert_eq!(2, target.control().switches());
+    assert_eq!(3, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_default_switch_stays_on_cas_uncontended() {
+    let target = Atomic::new(0, HybridAccessControl::new(1));
+    (0..1000).for_each(|_| target.write(|val| val + 1));
+
+    assert!(!target.control().is_locking());
+    assert_eq!(0, target.control().switches());
+    assert_eq!(1000, *target.read());
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_writes_across_switches(max_write_line in 1u16..6, spins in 1u32..8, window in 1u32..8, to_cas in 0u32..4, readers in 0usize..4, writers in 1usize..6, writes in 1usize..48) {
+        let switch = HybridSwitch::new(spins, window, to_cas + 1, to_cas);
+        let control = HybridAccessControl::with_switch(CASAccessControl::new(max_write_line), switch);
+        let target = Arc::new(Atomic::new(0usize, control));
+
+        common::run_increments(&target, readers, writers, writes, || ());
+        // Every switch flips the path, starting on CAS.
+        let control = target.control();
+        assert_eq!(u64::from(control.is_locking()), control.switches() % 2);
+    }
+}
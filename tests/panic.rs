@@ -0,0 +1,264 @@
+mod common;
+
+use lib::access::cas::CASAccessControl;
+use lib::atomic::{Atomic, PanicPolicy, WriteError};
+use std::panic::resume_unwind;
+
+#[cfg(not(loom))]
+use lib::access::AtomicAccessControl;
+#[cfg(not(loom))]
+use lib::access::lock::LockAccessControl;
+#[cfg(not(loom))]
+use lib::transaction::transaction;
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::panic::{AssertUnwindSafe, catch_unwind};
+#[cfg(not(loom))]
+use std::sync::Arc;
+#[cfg(not(loom))]
+use std::thread;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+// Skips the panic hook, injected panics would flood the output.
+fn injected_panic(_: &usize) -> usize {
+    resume_unwind(Box::new("injected panic"))
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_kept_value_after_panic() {
+    common::model(|| {
+        let control = common::parked(CASAccessControl::new(2));
+        let target = Arc::new(Atomic::new(0usize, control).with_panic_policy(PanicPolicy::KeepOld));
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 1))
+        };
+        let panicking = {
+            let target = target.clone();
+            thread::spawn(move || target.try_write(injected_panic))
+        };
+        target.write(|val| *val + 1);
+
+        assert_eq!(Err(WriteError::Panicked), panicking.join().expect(""));
+        reader.join().expect("");
+        assert_eq!(1, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+fn panic_in_writer_thread<A>(target: &Arc<Atomic<usize, A>>) -> bool
+where
+    A: AtomicAccessControl + Send + Sync + 'static,
+{
+    let target = target.clone();
+    thread::spawn(move || target.write(|_| panic!("writer panicked")))
+        .join()
+        .is_err()
+}
+
+#[cfg(not(loom))]
+fn propagated_panic<A>(control: A)
+where
+    A: AtomicAccessControl + Send + Sync + 'static,
+{
+    let target = Arc::new(Atomic::new(1usize, control));
+
+    assert!(panic_in_writer_thread(&target));
+    assert!(!target.is_poisoned());
+    assert_eq!(1, *target.read());
+    target.write(|val| val + 1);
+    assert_eq!(Ok(()), target.try_write(|val| val + 1));
+    assert_eq!(3, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_propagated_panic_releases_lock() {
+    propagated_panic(LockAccessControl::default());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_propagated_panic_releases_cas() {
+    propagated_panic(CASAccessControl::new(2));
+}
+
+#[cfg(not(loom))]
+fn poisoning_panic<A>(control: A)
+where
+    A: AtomicAccessControl + Send + Sync + 'static,
+{
+    let target = Arc::new(Atomic::new(1usize, control).with_panic_policy(PanicPolicy::Poison));
+
+    assert!(panic_in_writer_thread(&target));
+    assert!(target.is_poisoned());
+    assert_eq!(1, *target.read());
+    assert_eq!(Err(WriteError::Poisoned), target.try_write(|val| val + 1));
+
+    target.clear_poison();
+    assert!(!target.is_poisoned());
+    target.write(|val| val + 1);
+    assert_eq!(2, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_poisoned_lock_writes_fail_until_cleared() {
+    poisoning_panic(LockAccessControl::default());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_poisoned_cas_writes_fail_until_cleared() {
+    poisoning_panic(CASAccessControl::new(2));
+}
+
+#[cfg(not(loom))]
+#[test]
+#[should_panic(expected = "write to a poisoned atomic")]
+fn test_poisoned_write_panics() {
+    let target = Arc::new(Atomic::new_cas(0usize, 1).with_panic_policy(PanicPolicy::Poison));
+    assert!(panic_in_writer_thread(&target));
+    target.write(|val| val + 1);
+}
+
+#[cfg(not(loom))]
+fn caught_panic<A>(control: A)
+where
+    A: AtomicAccessControl + Send + Sync + 'static,
+{
+    let target = Arc::new(Atomic::new(1usize, control).with_panic_policy(PanicPolicy::KeepOld));
+
+    assert!(!panic_in_writer_thread(&target));
+    let writer = {
+        let target = target.clone();
+        thread::spawn(move || target.try_write(injected_panic))
+    };
+    assert_eq!(Err(WriteError::Panicked), writer.join().expect(""));
+    assert!(!target.is_poisoned());
+    assert_eq!(1, *target.read());
+
+    target.write(|val| val + 1);
+    assert_eq!(2, *target.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_caught_panic_keeps_lock_value() {
+    caught_panic(LockAccessControl::default());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_caught_panic_keeps_cas_value() {
+    caught_panic(CASAccessControl::new(2));
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_transaction_panics_follow_the_policy() {
+    let kept = Atomic::new_cas(1usize, 1).with_panic_policy(PanicPolicy::KeepOld);
+    let poisoned = Atomic::new_lock(1usize).with_panic_policy(PanicPolicy::Poison);
+    let other = Atomic::new_cas(1usize, 1);
+
+    transaction(|tx| {
+        tx.write(&other, |val| val + 1);
+        tx.write(&kept, injected_panic);
+    });
+    assert_eq!((1, 1), (*kept.read(), *other.read()));
+    assert!(!kept.is_poisoned());
+
+    let panicked = catch_unwind(AssertUnwindSafe(|| {
+        transaction(|tx| {
+            tx.write(&other, |val| val + 1);
+            tx.write(&poisoned, injected_panic);
+        })
+    }));
+    assert!(panicked.is_err());
+    assert!(poisoned.is_poisoned());
+    assert_eq!((1, 1), (*poisoned.read(), *other.read()));
+    assert_eq!(Err(WriteError::Poisoned), poisoned.try_write(|val| val + 1));
+
+    transaction(|tx| {
+        tx.write(&other, |val| val + 1);
+        tx.write(&kept, |val| val + 1);
+    });
+    assert_eq!((2, 2), (*kept.read(), *other.read()));
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_random_writer_panics(use_lock in proptest::bool::ANY, policy in 0u8..3, readers in 0usize..3, writers in 1usize..4, writes in 1usize..32, panic_every in 1usize..5) {
+        let policy = match policy {
+            0 => PanicPolicy::Propagate,
+            1 => PanicPolicy::Poison,
+            _ => PanicPolicy::KeepOld,
+        };
+        if use_lock {
+            random_writer_panics(LockAccessControl::default(), policy, readers, writers, writes, panic_every);
+        } else {
+            random_writer_panics(CASAccessControl::new(2), policy, readers, writers, writes, panic_every);
+        }
+    }
+}
+
+#[cfg(not(loom))]
+fn random_writer_panics<A>(
+    control: A,
+    policy: PanicPolicy,
+    readers: usize,
+    writers: usize,
+    writes: usize,
+    panic_every: usize,
+) where
+    A: AtomicAccessControl + Send + Sync + 'static,
+{
+    let target = Arc::new(Atomic::new(0usize, control).with_panic_policy(policy));
+
+    let written = common::run_load(
+        &target,
+        readers,
+        writers,
+        writes,
+        move |target, _, write| {
+            let update_fn: fn(&usize) -> usize = if (write + 1) % panic_every == 0 {
+                injected_panic
+            } else {
+                |val: &usize| val + 1
+            };
+            match catch_unwind(AssertUnwindSafe(|| target.try_write(update_fn))) {
+                Ok(Ok(())) => true,
+                Ok(Err(error)) => {
+                    assert!(matches!(
+                        (policy, error),
+                        (PanicPolicy::KeepOld, WriteError::Panicked)
+                            | (PanicPolicy::Poison, WriteError::Poisoned)
+                    ));
+                    false
+                }
+                Err(_) => {
+                    assert_ne!(PanicPolicy::KeepOld, policy);
+                    target.clear_poison();
+                    false
+                }
+            }
+        },
+        || (),
+    );
+    // Only a poisoned value turns away the writes that don't panic.
+    let landing = writers * (writes - writes / panic_every);
+    if policy == PanicPolicy::Poison {
+        assert!(written <= landing);
+    } else {
+        assert_eq!(landing, written);
+    }
+}
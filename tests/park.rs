@@ -0,0 +1,46 @@
+#![cfg(loom)]
+
+use lib::access::backoff::Park;
+use lib::access::cas::CASAccessControl;
+use lib::atomic::Atomic;
+use loom::sync::Arc;
+use loom::thread;
+
+fn parked_atomic(max_write_line: u16) -> Arc<Atomic<usize, CASAccessControl<Park>>> {
+    Arc::new(Atomic::new(
+        0,
+        CASAccessControl::new(max_write_line).with_backoff(Park::new(0)),
+    ))
+}
+
+#[test]
+fn test_loom_parked_writers_are_handed_off() {
+    loom::model(|| {
+        let target = parked_atomic(2);
+
+        let writer = {
+            let target = target.clone();
+            thread::spawn(move || target.write(|val| *val + 1))
+        };
+        target.write(|val| *val + 1);
+
+        writer.join().expect("");
+        assert_eq!(2, *target.read());
+    });
+}
+
+#[test]
+fn test_loom_parked_writer_woken_by_last_reader() {
+    loom::model(|| {
+        let target = parked_atomic(1);
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 1))
+        };
+        target.write(|val| *val + 1);
+
+        reader.join().expect("");
+        assert_eq!(1, *target.read());
+    });
+}
@@ -0,0 +1,238 @@
+#![cfg(not(loom))]
+
+mod common;
+
+use lib::access::AtomicAccessControl;
+use lib::access::cas::CASAccessControl;
+use lib::access::policy::PhasePolicy;
+use lib::atomic::Atomic;
+use proptest::proptest;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_writer_preferring_holds_back_new_readers() {
+    let control =
+        Arc::new(CASAccessControl::new(1).with_phase_policy(PhasePolicy::WriterPreferring));
+    let read = control.read();
+
+    let written = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let (control, written) = (control.clone(), written.clone());
+        thread::spawn(move || {
+            let _write = control.write();
+            written.store(true, Ordering::SeqCst);
+        })
+    };
+    // Let the writer become pending behind the active reader.
+    thread::sleep(Duration::from_millis(20));
+
+    let (sender, receiver) = mpsc::channel();
+    let reader = {
+        let (control, written) = (control.clone(), written.clone());
+        thread::spawn(move || {
+            let _read = control.read();
+            sender.send(written.load(Ordering::SeqCst)).expect("");
+        })
+    };
+
+    assert!(receiver.recv_timeout(Duration::from_millis(20)).is_err());
+    drop(read);
+    assert!(
+        receiver.recv().expect(""),
+        "reader went ahead of the pending writer"
+    );
+    writer.join().expect("");
+    reader.join().expect("");
+}
+
+#[test]
+fn test_reader_preferring_holds_back_writers() {
+    let control =
+        Arc::new(CASAccessControl::new(1).with_phase_policy(PhasePolicy::ReaderPreferring));
+    let read = control.read();
+
+    let (sender, receiver) = mpsc::channel();
+    let writer = {
+        let control = control.clone();
+        thread::spawn(move || {
+            let _write = control.write();
+            sender.send(()).expect("");
+        })
+    };
+
+    // New readers keep getting in while the writer waits.
+    thread::sleep(Duration::from_millis(20));
+    let another = control.read();
+    drop(read);
+    assert!(receiver.recv_timeout(Duration::from_millis(20)).is_err());
+
+    drop(another);
+    receiver.recv().expect("");
+    writer.join().expect("");
+}
+
+#[test]
+fn test_bounded_lets_writer_in_after_timeout() {
+    let control = Arc::new(
+        CASAccessControl::new(1).with_phase_policy(PhasePolicy::Bounded(Duration::from_millis(10))),
+    );
+    let read = control.read();
+
+    let written = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let (control, written) = (control.clone(), written.clone());
+        thread::spawn(move || {
+            let _write = control.write();
+            written.store(true, Ordering::SeqCst);
+        })
+    };
+    // Past his bound the writer opens the phase despite the active reader, so new readers queue behind it.
+    thread::sleep(Duration::from_millis(50));
+
+    let (sender, receiver) = mpsc::channel();
+    let reader = {
+        let (control, written) = (control.clone(), written.clone());
+        thread::spawn(move || {
+            let _read = control.read();
+            sender.send(written.load(Ordering::SeqCst)).expect("");
+        })
+    };
+
+    assert!(receiver.recv_timeout(Duration::from_millis(20)).is_err());
+    drop(read);
+    assert!(
+        receiver.recv().expect(""),
+        "reader went ahead of the expired writer"
+    );
+    writer.join().expect("");
+    reader.join().expect("");
+}
+
+proptest! {
+
+    #[test]
+    fn test_alternating(max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..4, writes in 1usize..64) {
+        perform(PhasePolicy::Alternating, max_write_line, readers, writers, writes);
+    }
+
+    #[test]
+    fn test_writer_preferring(max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..4, writes in 1usize..64) {
+        perform(PhasePolicy::WriterPreferring, max_write_line, readers, writers, writes);
+    }
+
+    #[test]
+    fn test_reader_preferring(max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..4, writes in 1usize..64) {
+        perform(PhasePolicy::ReaderPreferring, max_write_line, readers, writers, writes);
+    }
+
+    #[test]
+    fn test_bounded(max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..4, writes in 1usize..64) {
+        perform(PhasePolicy::Bounded(Duration::from_micros(50)), max_write_line, readers, writers, writes);
+    }
+
+    // A reader pending behind the running phase may still lose a race with the next initiator, he reads before the one after.
+    #[test]
+    fn test_alternating_waits(max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..8) {
+        let (reads, phases) = waited_phases(PhasePolicy::Alternating, max_write_line, readers, writers);
+        assert!(reads.iter().all(|read| *read <= usize::from(max_write_line)));
+        assert!(phases.iter().all(|phase| *phase <= writer_phases(max_write_line, writers)));
+    }
+
+    #[test]
+    fn test_writer_preferring_waits(max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..8) {
+        let (reads, phases) = waited_phases(PhasePolicy::WriterPreferring, max_write_line, readers, writers);
+        assert!(reads.iter().all(|read| *read == writers));
+        assert!(phases.iter().all(|phase| *phase <= writer_phases(max_write_line, writers)));
+    }
+
+    #[test]
+    fn test_reader_preferring_waits(max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..8) {
+        let (reads, phases) = waited_phases(PhasePolicy::ReaderPreferring, max_write_line, readers, writers);
+        assert!(reads.iter().all(|read| *read == 0));
+        assert!(phases.iter().all(|phase| *phase <= writer_phases(max_write_line, writers)));
+    }
+
+    #[test]
+    fn test_bounded_waits(max_write_line in 1u16..4, bound in 0u64..200, readers in 1usize..4, writers in 1usize..8) {
+        let policy = PhasePolicy::Bounded(Duration::from_micros(bound));
+        let (reads, phases) = waited_phases(policy, max_write_line, readers, writers);
+        assert!(reads.iter().all(|read| *read <= usize::from(max_write_line)));
+        assert!(phases.iter().all(|phase| *phase <= writer_phases(max_write_line, writers)));
+    }
+}
+
+// Phases it takes to drain `writers` pending at once, each one opened for as many of them as the line allows.
+fn writer_phases(max_write_line: u16, writers: usize) -> u64 {
+    writers.div_ceil(usize::from(max_write_line)) as u64
+}
+
+// Readers and writers all pending behind a held write, released at once. Returns the value each reader read and the
+// phase, counted from the held one, each writer wrote in.
+fn waited_phases(
+    policy: PhasePolicy,
+    max_write_line: u16,
+    readers: usize,
+    writers: usize,
+) -> (Vec<usize>, Vec<u64>) {
+    let target = Arc::new(Atomic::new(
+        0usize,
+        CASAccessControl::new(max_write_line).with_phase_policy(policy),
+    ));
+    let writing = target.control().write();
+    let held = target.control().write_phases();
+
+    let reader_workers: Vec<_> = (0..readers)
+        .map(|_| {
+            let target = target.clone();
+            thread::spawn(move || *target.read())
+        })
+        .collect();
+    while target.control().pending_readers() < readers as u64 {
+        thread::yield_now();
+    }
+    let writer_workers: Vec<_> = (0..writers)
+        .map(|_| {
+            let target = target.clone();
+            thread::spawn(move || {
+                let phase = AtomicU64::new(0);
+                target.write(|val| {
+                    phase.store(target.control().write_phases() - held, Ordering::Relaxed);
+                    *val + 1
+                });
+                phase.into_inner()
+            })
+        })
+        .collect();
+    while target.control().pending_writers() < writers as u64 {
+        thread::yield_now();
+    }
+    drop(writing);
+
+    let phases = writer_workers
+        .into_iter()
+        .map(|worker| worker.join().expect(""))
+        .collect();
+    let reads = reader_workers
+        .into_iter()
+        .map(|worker| worker.join().expect(""))
+        .collect();
+    assert_eq!(writers, *target.read());
+    (reads, phases)
+}
+
+fn perform(
+    policy: PhasePolicy,
+    max_write_line: u16,
+    readers: usize,
+    writers: usize,
+    writes: usize,
+) {
+    let target = Arc::new(Atomic::new(
+        0usize,
+        CASAccessControl::new(max_write_line).with_phase_policy(policy),
+    ));
+    common::run_increments(&target, readers, writers, writes, || ());
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use lib::access::cas::CASAccessControl;
+use lib::access::policy::Priority;
+use lib::atomic::Atomic;
+
+#[cfg(not(loom))]
+use lib::access::lock::LockAccessControl;
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::{Arc, mpsc};
+#[cfg(not(loom))]
+use std::thread;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(loom)]
+#[test]
+fn test_loom_high_and_normal_writers() {
+    common::model(|| {
+        let target = Arc::new(Atomic::new(0, common::parked(CASAccessControl::new(2))));
+
+        let high = {
+            let target = target.clone();
+            thread::spawn(move || target.write_priority(Priority::High, |val| *val + 1))
+        };
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 2))
+        };
+        target.write(|val| *val + 1);
+
+        high.join().expect("");
+        reader.join().expect("");
+        assert_eq!(2, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+fn push(id: usize) -> impl Fn(&Vec<usize>) -> Vec<usize> {
+    move |val| {
+        let mut val = val.clone();
+        val.push(id);
+        val
+    }
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_high_priority_jumps_pending_writers() {
+    let target = Arc::new(Atomic::new(vec![], CASAccessControl::new(1)));
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let blocker = {
+        let target = target.clone();
+        thread::spawn(move || {
+            target.write(|val| {
+                started_tx.send(()).expect("");
+                release_rx.recv().expect("");
+                val.clone()
+            })
+        })
+    };
+    started_rx.recv().expect("");
+
+    let writers: Vec<_> = (0..4)
+        .map(|id| {
+            let target = target.clone();
+            thread::spawn(move || target.write(push(id)))
+        })
+        .collect();
+    while target.control().pending_writers() < 4 {
+        thread::yield_now();
+    }
+    let high = {
+        let target = target.clone();
+        thread::spawn(move || target.write_priority(Priority::High, push(99)))
+    };
+    // Counted as high only after his pending increment, the normal writers would win the phase in between.
+    while target.control().pending_high() < 1 {
+        thread::yield_now();
+    }
+
+    release_tx.send(()).expect("");
+    blocker.join().expect("");
+    high.join().expect("");
+    writers
+        .into_iter()
+        .for_each(|writer| writer.join().expect(""));
+
+    assert_eq!(99, target.read()[0]);
+    assert_eq!(5, target.read().len());
+    assert_eq!(1, target.control().queue_jumps());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_no_jump_without_pending_writers() {
+    let target = Atomic::new(0, CASAccessControl::new(1));
+    target.write_priority(Priority::High, |val| val + 1);
+    assert_eq!(1, *target.read());
+    assert_eq!(0, target.control().queue_jumps());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_priority_ignored_without_support() {
+    let target = Atomic::new(0, LockAccessControl::default());
+    target.write_priority(Priority::High, |val| val + 1);
+    assert_eq!(1, *target.read());
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_mixed_priority_writes(max_write_line in 1u16..8, fifo in proptest::bool::ANY, readers in 1usize..4, writers in 1usize..4, high_writers in 1usize..4, writes in 1usize..32) {
+        let control = CASAccessControl::new(max_write_line);
+        let control = if fifo { control.with_fifo_writers() } else { control };
+        let target = Arc::new(Atomic::new(0usize, control));
+
+        let written = common::run_load(
+            &target,
+            readers,
+            writers + high_writers,
+            writes,
+            move |target, writer, _| {
+                let priority = if writer < writers { Priority::Normal } else { Priority::High };
+                target.write_priority(priority, |val| *val + 1);
+                true
+            },
+            || (),
+        );
+        assert_eq!((writers + high_writers) * writes, written);
+        // Only high priority writes jump the queue, and none of the FIFO one.
+        let jumps = target.control().queue_jumps();
+        assert!(jumps <= (high_writers * writes) as u64);
+        if fifo {
+            assert_eq!(0, jumps);
+        }
+    }
+}
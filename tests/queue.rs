@@ -0,0 +1,110 @@
+mod common;
+
+use lib::access::cas::CASAccessControl;
+use lib::atomic::Atomic;
+
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::Arc;
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(loom)]
+use lib::access::backoff::Park;
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(loom)]
+fn parked_queued_atomic(max_write_line: u16) -> Arc<Atomic<usize, CASAccessControl<Park>>> {
+    let control = CASAccessControl::new(max_write_line).with_queued_writers();
+    Arc::new(Atomic::new(0, common::parked(control)))
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_queued_writers_of_one_phase() {
+    common::model(|| {
+        let target = parked_queued_atomic(3);
+
+        let writers: Vec<_> = (0..2)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || target.write(|val| *val + 1))
+            })
+            .collect();
+        target.write(|val| *val + 1);
+
+        writers
+            .into_iter()
+            .for_each(|writer| writer.join().expect(""));
+        assert_eq!(3, *target.read());
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_queued_writers_across_phases() {
+    loom::model(|| {
+        let target = parked_queued_atomic(1);
+
+        let writer = {
+            let target = target.clone();
+            thread::spawn(move || {
+                target.write(|val| *val + 1);
+                target.write(|val| *val + 1);
+            })
+        };
+        target.write(|val| *val + 1);
+
+        writer.join().expect("");
+        assert_eq!(3, *target.read());
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_queued_writer_and_reader() {
+    loom::model(|| {
+        let target = parked_queued_atomic(2);
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 1))
+        };
+        target.write(|val| *val + 1);
+
+        reader.join().expect("");
+        assert_eq!(1, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_queued_writes(max_write_line in 1u16..8, readers in 0usize..4, writers in 1usize..6, writes in 1usize..64) {
+        let target = Arc::new(Atomic::new(0usize, CASAccessControl::new(max_write_line).with_queued_writers()));
+        let writing = Arc::new(AtomicBool::new(false));
+
+        common::run_load(
+            &target,
+            readers,
+            writers,
+            writes,
+            move |target, _, _| {
+                target.write(|val| {
+                    // A hand-off never lets the next writer in before the previous one is done.
+                    assert!(!writing.swap(true, Ordering::SeqCst));
+                    writing.store(false, Ordering::SeqCst);
+                    *val + 1
+                });
+                true
+            },
+            || (),
+        );
+        assert_eq!(0, target.control().pending_writers());
+    }
+}
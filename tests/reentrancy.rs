@@ -0,0 +1,109 @@
+#![cfg(not(loom))]
+
+use lib::atomic::{Atomic, PanicPolicy};
+use lib::transaction::transaction;
+use proptest::proptest;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+use std::thread;
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "atomic accessed from inside its own write closure")]
+fn test_read_inside_cas_write_panics() {
+    let target = Atomic::new_cas(0, 1);
+    target.write(|val| val + *target.read());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "atomic accessed from inside its own write closure")]
+fn test_write_inside_lock_write_panics() {
+    let target = Atomic::new_lock(0);
+    target.write(|val| {
+        target.write(|val| val + 1);
+        val + 1
+    });
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "atomic accessed from inside its own write closure")]
+fn test_read_inside_transaction_write_panics() {
+    let first = Atomic::new_cas(0, 1);
+    let second = Atomic::new_cas(0, 1);
+    transaction(|tx| {
+        tx.write(&first, |val| val + 1);
+        tx.write(&second, |val| val + *first.read());
+    });
+}
+
+#[test]
+fn test_other_atomics_inside_write() {
+    let first = Atomic::new_cas(1, 1);
+    let second = Atomic::new_lock(2);
+    first.write(|val| {
+        second.write(|other| other + 1);
+        val + *second.read()
+    });
+    assert_eq!(4, *first.read());
+    assert_eq!(3, *second.read());
+}
+
+#[test]
+fn test_access_released_after_panicking_write() {
+    let propagated = Atomic::new_cas(0, 1);
+    let kept = Atomic::new_lock(0).with_panic_policy(PanicPolicy::KeepOld);
+
+    assert!(
+        catch_unwind(AssertUnwindSafe(
+            || propagated.write(|_| panic!("writer panicked"))
+        ))
+        .is_err()
+    );
+    kept.write(|_| panic!("writer panicked"));
+
+    propagated.write(|val| val + 1);
+    kept.write(|val| val + 1);
+    assert_eq!(1, *propagated.read());
+    assert_eq!(1, *kept.read());
+}
+
+#[test]
+fn test_read_in_write_returns_replaced_value() {
+    let target = Atomic::new_cas(1, 1);
+    assert_eq!(1, *target.read_in_write(|val| val + 1));
+    assert_eq!(2, *target.read_in_write(|val| val * 3));
+    assert_eq!(6, *target.read());
+
+    let kept = Atomic::new_lock(1).with_panic_policy(PanicPolicy::KeepOld);
+    assert_eq!(1, *kept.read_in_write(|_| panic!("writer panicked")));
+    assert_eq!(1, *kept.read());
+}
+
+proptest! {
+
+    #[test]
+    fn test_read_in_write_sees_every_version_once(max_write_line in 1u16..6, writers in 1usize..5, writes in 1usize..48) {
+        let target = Arc::new(Atomic::new_cas(0usize, max_write_line));
+
+        let workers: Vec<_> = (0..writers)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || {
+                    (0..writes)
+                        .map(|_| *target.read_in_write(|val| val + 1))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut replaced: Vec<usize> = workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect(""))
+            .collect();
+        replaced.sort_unstable();
+        assert_eq!((0..writers * writes).collect::<Vec<_>>(), replaced);
+        assert_eq!(writers * writes, *target.read());
+    }
+}
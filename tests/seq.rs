@@ -0,0 +1,71 @@
+#![cfg(not(loom))]
+
+use lib::access::lock::LockAccessControl;
+use lib::seq::AtomicSeq;
+use proptest::proptest;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn test_read_returns_value() {
+    let target = AtomicSeq::new_cas((1u8, 'a'), 1);
+    assert_eq!((1, 'a'), target.read());
+
+    target.write(|(num, _)| (num + 1, 'b'));
+    assert_eq!((2, 'b'), target.read());
+    assert_eq!(((2, 'b'), 2), target.read_versioned());
+}
+
+#[test]
+fn test_lock_writers() {
+    let target = Arc::new(AtomicSeq::new(0u64, LockAccessControl::default()));
+
+    let writers: Vec<_> = (0..4)
+        .map(|_| {
+            let target = target.clone();
+            thread::spawn(move || (0..1000).for_each(|_| target.write(|val| val + 1)))
+        })
+        .collect();
+    writers
+        .into_iter()
+        .for_each(|writer| writer.join().expect(""));
+
+    assert_eq!(4000, target.read());
+    assert_eq!(8000, target.version());
+}
+
+proptest! {
+
+    #[test]
+    fn test_reads_never_torn(max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..4, writes in 1usize..256) {
+        let target = Arc::new(AtomicSeq::new_cas([0u64; 8], max_write_line));
+
+        let reader_workers: Vec<_> = (0..readers)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || {
+                    let mut last = 0;
+                    for _ in 0..writes {
+                        let current = target.read();
+                        assert!(current.iter().all(|word| *word == current[0]));
+                        assert!(last <= current[0]);
+                        last = current[0];
+                        thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+        let writer_workers: Vec<_> = (0..writers)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || (0..writes).for_each(|_| target.write(|val| [val[0] + 1; 8])))
+            })
+            .collect();
+
+        writer_workers
+            .into_iter()
+            .chain(reader_workers)
+            .for_each(|worker| worker.join().expect(""));
+        assert_eq!([(writers * writes) as u64; 8], target.read());
+    }
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use lib::access::cas::CASAccessControl;
+use lib::access::striped::StripedAccessControl;
+use lib::atomic::Atomic;
+
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::sync::Arc;
+#[cfg(not(loom))]
+use std::thread;
+#[cfg(not(loom))]
+use std::time::Duration;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+#[cfg(loom)]
+#[test]
+fn test_loom_biased_reader_and_writer() {
+    loom::model(|| {
+        let control =
+            StripedAccessControl::with_stripes(common::parked(CASAccessControl::new(1)), 2);
+        let target = Arc::new(Atomic::new(0, control));
+
+        let reader = {
+            let target = target.clone();
+            thread::spawn(move || assert!(*target.read() <= 1))
+        };
+        target.write(|val| *val + 1);
+
+        reader.join().expect("");
+        assert_eq!(1, *target.read());
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_write_revokes_and_slow_read_restores_bias() {
+    let target = Atomic::new(0, StripedAccessControl::new(1));
+    assert!(target.control().is_read_biased());
+    assert_eq!(0, *target.read());
+
+    target.write(|val| val + 1);
+    assert!(!target.control().is_read_biased());
+
+    // Past the inhibition the next slow read hands reads back to the stripes.
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(1, *target.read());
+    assert!(target.control().is_read_biased());
+    assert_eq!(1, *target.read());
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_striped_reads_and_writes(stripes in 1usize..8, max_write_line in 1u16..4, readers in 1usize..4, writers in 1usize..4, writes in 1usize..64) {
+        let control = StripedAccessControl::with_stripes(CASAccessControl::new(max_write_line), stripes);
+        let target = Arc::new(Atomic::new(0usize, control));
+
+        common::run_increments(&target, readers, writers, writes, || ());
+        // However the stripes ended up, a write takes the bias away.
+        target.write(|val| *val + 1);
+        assert!(!target.control().is_read_biased());
+        assert_eq!(writers * writes + 1, *target.read());
+    }
+}
@@ -0,0 +1,266 @@
+use lib::access::AtomicAccessControl;
+use lib::atomic::Atomic;
+use lib::transaction::optimistic;
+
+#[cfg(not(loom))]
+use lib::group::AtomicGroup;
+#[cfg(not(loom))]
+use lib::snapshot;
+#[cfg(not(loom))]
+use lib::transaction::transaction;
+#[cfg(not(loom))]
+use proptest::proptest;
+#[cfg(not(loom))]
+use std::collections::HashMap;
+#[cfg(not(loom))]
+use std::panic::{AssertUnwindSafe, catch_unwind};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(not(loom))]
+use std::sync::{Arc, Barrier};
+#[cfg(not(loom))]
+use std::thread;
+
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(loom)]
+use loom::thread;
+
+// Takes its own atomic off duty while the other stays on, of two running concurrently only one may commit.
+fn go_off_duty<A: AtomicAccessControl>(
+    own: &Atomic<i32, A>,
+    other: &Atomic<i32, A>,
+    both_read: impl Fn(),
+) {
+    optimistic(|tx| {
+        let (own_val, other_val) = (tx.read(own), tx.read(other));
+        both_read();
+        if *own_val + *other_val == 2 {
+            tx.write(own, 0);
+        }
+    })
+}
+
+#[cfg(loom)]
+#[test]
+fn test_loom_optimistic_prevents_write_skew() {
+    loom::model(|| {
+        let a = Arc::new(Atomic::new_cas(1, 1));
+        let b = Arc::new(Atomic::new_cas(1, 1));
+
+        let other = {
+            let (a, b) = (a.clone(), b.clone());
+            thread::spawn(move || go_off_duty(&*b, &*a, || ()))
+        };
+        go_off_duty(&*a, &*b, || ());
+
+        other.join().expect("");
+        assert_eq!(1, *a.read() + *b.read());
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_transaction_applies_writes_to_same_atomic_in_order() {
+    let a = Atomic::new_cas(1, 1);
+    let b = Atomic::new_lock(0);
+
+    let staged = transaction(|tx| {
+        tx.write(&a, |val| *val + 1);
+        tx.write(&b, |val| *val + 5);
+        tx.write(&a, |val| *val * 10);
+        3
+    });
+
+    assert_eq!(3, staged);
+    assert_eq!(20, *a.read());
+    assert_eq!(5, *b.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_transaction_keeps_derived_index_in_sync() {
+    let group = AtomicGroup::new();
+    let users = Arc::new(Atomic::new_cas(Vec::<String>::new(), 4).in_group(&group));
+    let index = Arc::new(Atomic::new_cas(HashMap::<String, usize>::new(), 4).in_group(&group));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let reader = {
+        let (users, index, done) = (users.clone(), index.clone(), done.clone());
+        thread::spawn(move || {
+            while !done.load(Ordering::Acquire) {
+                let (users, index) = snapshot!(*users, *index);
+                assert_eq!(users.len(), index.len());
+                users
+                    .iter()
+                    .enumerate()
+                    .for_each(|(pos, user)| assert_eq!(Some(&pos), index.get(user)));
+            }
+        })
+    };
+
+    (0..500).for_each(|i| {
+        let user = format!("user-{i}");
+        transaction(|tx| {
+            let name = user.clone();
+            tx.write(&*users, move |users| {
+                let mut users = users.clone();
+                users.push(name);
+                users
+            });
+            tx.write(&*index, move |index| {
+                let mut index = index.clone();
+                index.insert(user, index.len());
+                index
+            });
+        });
+    });
+
+    done.store(true, Ordering::Release);
+    reader.join().expect("");
+    assert_eq!(500, users.read().len());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_panicking_transaction_commits_nothing() {
+    let group = AtomicGroup::new();
+    let a = Atomic::new_cas(1, 1).in_group(&group);
+    let b = Atomic::new_lock(2).in_group(&group);
+
+    let panicked = catch_unwind(AssertUnwindSafe(|| {
+        transaction(|tx| {
+            tx.write(&a, |val| *val + 1);
+            tx.write(&a, |val| *val * 10);
+            tx.write(&b, |_| panic!("update panicked"));
+        })
+    }));
+
+    assert!(panicked.is_err());
+    assert_eq!((1, 2), group.read_with(|| (*a.read(), *b.read())));
+    transaction(|tx| {
+        tx.write(&a, |val| *val + 1);
+        tx.write(&b, |val| *val + 1);
+    });
+    let (a, b) = snapshot!(a, b);
+    assert_eq!((2, 3), (*a, *b));
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_optimistic_retries_on_conflict() {
+    let a = Atomic::new_cas(0, 1);
+    let b = Atomic::new_lock(0);
+    let attempts = AtomicUsize::new(0);
+
+    optimistic(|tx| {
+        let val = tx.read(&a);
+        // A write sneaking in between the read and the commit invalidates the first attempt.
+        if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+            a.write(|val| *val + 100);
+        }
+        tx.write(&b, *val + 1);
+    });
+
+    assert_eq!(2, attempts.load(Ordering::SeqCst));
+    assert_eq!(101, *b.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_optimistic_blind_writes_never_conflict() {
+    let a = Atomic::new_cas(0, 1);
+    let attempts = AtomicUsize::new(0);
+
+    optimistic(|tx| {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        a.write(|val| *val + 1);
+        tx.write(&a, 10);
+    });
+
+    assert_eq!(1, attempts.load(Ordering::SeqCst));
+    assert_eq!(10, *a.read());
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_optimistic_prevents_write_skew() {
+    (0..1000).for_each(|_| {
+        let (a, b) = (Atomic::new_cas(1, 1), Atomic::new_cas(1, 1));
+        let both_read = Barrier::new(2);
+
+        // Only the first attempts wait for each other, the retry of the loser must not.
+        let first_read = |first: &AtomicBool| {
+            if first.swap(false, Ordering::SeqCst) {
+                both_read.wait();
+            }
+        };
+        let (a_first, b_first) = (AtomicBool::new(true), AtomicBool::new(true));
+        thread::scope(|s| {
+            s.spawn(|| go_off_duty(&a, &b, || first_read(&a_first)));
+            s.spawn(|| go_off_duty(&b, &a, || first_read(&b_first)));
+        });
+
+        assert_eq!(1, *a.read() + *b.read());
+    });
+}
+
+#[cfg(not(loom))]
+proptest! {
+
+    #[test]
+    fn test_optimistic_transfers_are_serializable(num_workers in 2usize..6, num_worker_txs in 100usize..1000) {
+        let total = num_workers * num_worker_txs;
+        let from = Arc::new(Atomic::new_cas(total, 1));
+        let to = Arc::new(Atomic::new_lock(0usize));
+
+        let workers: Vec<_> = (0..num_workers)
+            .map(|_| {
+                let (from, to) = (from.clone(), to.clone());
+                thread::spawn(move || {
+                    (0..num_worker_txs).for_each(|_| {
+                        optimistic(|tx| {
+                            let (from_val, to_val) = (tx.read(&*from), tx.read(&*to));
+                            tx.write(&*from, *from_val - 1);
+                            tx.write(&*to, *to_val + 1);
+                        })
+                    })
+                })
+            })
+            .collect();
+        workers.into_iter().for_each(|worker| worker.join().expect(""));
+
+        assert_eq!(0, *from.read());
+        assert_eq!(total, *to.read());
+    }
+
+    #[test]
+    fn test_crossed_transactions_do_not_deadlock(num_workers in 2usize..6, num_worker_txs in 100usize..1000) {
+        let a = Arc::new(Atomic::new_cas(0usize, 2));
+        let b = Arc::new(Atomic::new_lock(0usize));
+
+        let workers: Vec<_> = (0..num_workers)
+            .map(|worker| {
+                let (a, b) = (a.clone(), b.clone());
+                thread::spawn(move || {
+                    (0..num_worker_txs).for_each(|_| {
+                        transaction(|tx| {
+                            // Half of the workers stage the atomics in the opposite order.
+                            if worker % 2 == 0 {
+                                tx.write(&*a, |val| *val + 1);
+                                tx.write(&*b, |val| *val + 1);
+                            } else {
+                                tx.write(&*b, |val| *val + 1);
+                                tx.write(&*a, |val| *val + 1);
+                            }
+                        })
+                    })
+                })
+            })
+            .collect();
+        workers.into_iter().for_each(|worker| worker.join().expect(""));
+
+        assert_eq!(num_workers * num_worker_txs, *a.read());
+        assert_eq!(num_workers * num_worker_txs, *b.read());
+    }
+}
@@ -0,0 +1,123 @@
+#![cfg(not(loom))]
+
+use lib::atomic::Atomic;
+use lib::undo::UndoableAtomic;
+use proptest::proptest;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn test_undo_redo_walks_committed_versions() {
+    let target = UndoableAtomic::new(Atomic::new_cas(0, 1), 8);
+    (0..3).for_each(|_| target.write(|val| *val + 1));
+
+    assert!(target.undo());
+    assert!(target.undo());
+    assert_eq!(1, *target.read());
+
+    assert!(target.redo());
+    assert_eq!(2, *target.read());
+
+    assert!(target.undo());
+    assert!(target.undo());
+    assert!(!target.undo());
+    assert_eq!(0, *target.read());
+}
+
+#[test]
+fn test_write_invalidates_redo() {
+    let target = UndoableAtomic::new(Atomic::new_lock(0), 8);
+    target.write(|val| *val + 1);
+    target.write(|val| *val + 1);
+
+    assert!(target.undo());
+    target.write(|val| *val + 10);
+
+    assert!(!target.can_redo());
+    assert!(!target.redo());
+    assert_eq!(11, *target.read());
+}
+
+#[test]
+fn test_clear_history_keeps_current() {
+    let target = UndoableAtomic::new(Atomic::new_cas(0, 1), 8);
+    target.write(|val| *val + 1);
+    target.write(|val| *val + 1);
+    assert!(target.undo());
+
+    target.clear_history();
+
+    assert!(!target.can_undo());
+    assert!(!target.can_redo());
+    assert_eq!(1, *target.read());
+}
+
+#[test]
+fn test_undo_redo_on_frozen_atomic() {
+    let target = UndoableAtomic::new(Atomic::new_cas(0, 1), 8);
+    target.write(|val| *val + 1);
+    target.write(|val| *val + 1);
+    assert!(target.undo());
+
+    target.freeze();
+    assert!(!target.undo());
+    assert!(!target.redo());
+    assert_eq!(1, *target.read());
+    // Nothing restored, nothing lost.
+    assert!(target.can_undo());
+    assert!(target.can_redo());
+}
+
+#[test]
+fn test_undo_redo_on_closed_atomic() {
+    let target = UndoableAtomic::new(Atomic::new_lock(0), 8);
+    target.write(|val| *val + 1);
+    target.write(|val| *val + 1);
+    assert!(target.undo());
+
+    target.close();
+    assert!(!target.undo());
+    assert!(!target.redo());
+    assert_eq!(1, *target.read());
+    assert!(target.can_undo());
+    assert!(target.can_redo());
+}
+
+proptest! {
+
+    #[test]
+    fn test_undo_bounded_by_depth(depth in 1usize..16, writes in 0usize..32) {
+        let target = UndoableAtomic::new(Atomic::new_cas(0, 1), depth);
+        (0..writes).for_each(|_| target.write(|val| *val + 1));
+
+        let mut undone = 0;
+        while target.undo() {
+            undone += 1;
+        }
+
+        assert_eq!(writes.min(depth), undone);
+        assert_eq!(writes - undone, *target.read());
+    }
+
+    #[test]
+    fn test_concurrent_writes_undo_back_to_initial(num_writers in 2usize..6, num_worker_writes in 10usize..100) {
+        let target = Arc::new(UndoableAtomic::new(Atomic::new_cas(0usize, u16::MAX), num_writers * num_worker_writes));
+
+        let workers: Vec<_> = (0..num_writers)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || (0..num_worker_writes).for_each(|_| target.write(|val| *val + 1)))
+            })
+            .collect();
+        workers.into_iter().for_each(|worker| worker.join().expect(""));
+
+        // Every recorded version must be exactly one write behind the next one.
+        let mut expected = num_writers * num_worker_writes;
+        assert_eq!(expected, *target.read());
+        while target.undo() {
+            expected -= 1;
+            assert_eq!(expected, *target.read());
+        }
+        assert_eq!(0, expected);
+    }
+}
@@ -0,0 +1,153 @@
+#![cfg(not(loom))]
+
+use lib::access::AtomicAccessControl;
+use lib::access::cas::CASAccessControl;
+use lib::access::policy::{AdaptiveWriteLine, PhasePolicy};
+use lib::atomic::Atomic;
+use proptest::proptest;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+#[should_panic]
+fn test_zero_write_line_is_rejected() {
+    CASAccessControl::new(1).set_max_write_line(0);
+}
+
+#[test]
+#[should_panic]
+fn test_empty_adaptive_bounds_are_rejected() {
+    AdaptiveWriteLine::new(4, 2);
+}
+
+#[test]
+fn test_adaptive_clamps_initial_write_line() {
+    let control = CASAccessControl::new(32).with_adaptive_write_line(AdaptiveWriteLine::new(1, 8));
+    assert_eq!(8, control.max_write_line());
+}
+
+#[test]
+fn test_adaptive_doubles_under_write_bursts() {
+    let control = CASAccessControl::new(1).with_adaptive_write_line(AdaptiveWriteLine::new(1, 8));
+    let target = Arc::new(Atomic::new(0, control));
+    let writing = target.control().write();
+
+    let writers: Vec<_> = (0..4)
+        .map(|_| {
+            let target = target.clone();
+            thread::spawn(move || target.write(|val| val + 1))
+        })
+        .collect();
+    while target.control().pending_writers() < 4 {
+        thread::yield_now();
+    }
+    drop(writing);
+    writers
+        .into_iter()
+        .for_each(|writer| writer.join().expect(""));
+
+    // Four writers past a line of one double it, the three left past two double it again.
+    assert_eq!(4, target.control().max_write_line());
+    assert_eq!(4, *target.read());
+}
+
+#[test]
+fn test_adaptive_halves_when_readers_wait() {
+    let control = CASAccessControl::new(8)
+        .with_phase_policy(PhasePolicy::WriterPreferring)
+        .with_adaptive_write_line(AdaptiveWriteLine::new(1, 8));
+    let target = Arc::new(Atomic::new(0, control));
+    let writing = target.control().write();
+
+    // Holding back for the pending writer, the reader is still waiting when his phase opens.
+    let reader = {
+        let target = target.clone();
+        thread::spawn(move || *target.read())
+    };
+    thread::sleep(Duration::from_millis(10));
+    let writer = {
+        let target = target.clone();
+        thread::spawn(move || target.write(|val| val + 1))
+    };
+    while target.control().pending_writers() < 1 {
+        thread::yield_now();
+    }
+    drop(writing);
+    writer.join().expect("");
+    assert_eq!(1, reader.join().expect(""));
+
+    assert_eq!(4, target.control().max_write_line());
+}
+
+proptest! {
+
+    #[test]
+    fn test_set_max_write_line_while_writing(lines in proptest::collection::vec(1u16..8, 1..32), writers in 1usize..4, writes in 1usize..64) {
+        let target = Arc::new(Atomic::new(0usize, CASAccessControl::new(1)));
+        let done = Arc::new(AtomicBool::new(false));
+
+        let tuner = {
+            let (target, done) = (target.clone(), done.clone());
+            let lines = lines.clone();
+            thread::spawn(move || {
+                // Sets a line before looking at `done`, the writers may be done before he starts.
+                for line in lines.iter().cycle() {
+                    target.control().set_max_write_line(*line);
+                    if done.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    thread::yield_now();
+                }
+            })
+        };
+        let workers: Vec<_> = (0..writers)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || (0..writes).for_each(|_| target.write(|val| *val + 1)))
+            })
+            .collect();
+
+        workers.into_iter().for_each(|worker| worker.join().expect(""));
+        done.store(true, Ordering::Relaxed);
+        tuner.join().expect("");
+        assert_eq!(writers * writes, *target.read());
+        assert!(lines.contains(&target.control().max_write_line()));
+    }
+
+    #[test]
+    fn test_adaptive_write_line_stays_in_bounds(min in 1u16..4, extra in 0u16..8, readers in 0usize..4, writers in 1usize..4, writes in 1usize..64) {
+        let adaptive = AdaptiveWriteLine::new(min, min + extra);
+        let target = Arc::new(Atomic::new(
+            0usize,
+            CASAccessControl::new(1).with_adaptive_write_line(adaptive),
+        ));
+
+        let reader_workers: Vec<_> = (0..readers)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || {
+                    for _ in 0..writes {
+                        let line = target.control().max_write_line();
+                        assert!(adaptive.min() <= line && line <= adaptive.max());
+                        drop(target.read());
+                        thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+        let writer_workers: Vec<_> = (0..writers)
+            .map(|_| {
+                let target = target.clone();
+                thread::spawn(move || (0..writes).for_each(|_| target.write(|val| *val + 1)))
+            })
+            .collect();
+
+        writer_workers
+            .into_iter()
+            .chain(reader_workers)
+            .for_each(|worker| worker.join().expect(""));
+        assert_eq!(writers * writes, *target.read());
+    }
+}